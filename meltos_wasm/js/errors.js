@@ -0,0 +1,47 @@
+class TvcError extends Error {
+    constructor(kind, message, details, cause) {
+        super(message, cause === undefined ? undefined : { cause });
+        this.name = `${kind}Error`;
+        this.kind = kind;
+        this.details = details;
+    }
+}
+
+function errorOfKind(kind) {
+    return class extends TvcError {
+        constructor(message, details, cause) {
+            super(kind, message, details, cause);
+        }
+    };
+}
+
+const classes = {
+    Unknown: TvcError,
+    NotFound: errorOfKind("NotFound"),
+    NothingToCommit: errorOfKind("NothingToCommit"),
+    PushRejected: errorOfKind("PushRejected"),
+    Conflict: errorOfKind("Conflict"),
+    Unauthorized: errorOfKind("Unauthorized"),
+    RoomClosed: errorOfKind("RoomClosed"),
+    Network: errorOfKind("Network"),
+    Io: errorOfKind("Io"),
+    Corrupted: errorOfKind("Corrupted"),
+    InvalidOperation: errorOfKind("InvalidOperation"),
+};
+for (const [kind, Class] of Object.entries(classes)) {
+    Object.defineProperty(Class, "name", { value: `${kind === "Unknown" ? "Tvc" : kind}Error` });
+}
+
+function create_error(kind, message, details, cause) {
+    const Class = classes[kind] ?? TvcError;
+    return Class === TvcError ? new TvcError(kind, message, details, cause) : new Class(message, details, cause);
+}
+
+function error_classes() {
+    return classes;
+}
+
+module.exports = {
+    create_error,
+    error_classes
+}
@@ -0,0 +1,6 @@
+function report_error(error) {
+    queueMicrotask(() => { throw error; });
+}
+module.exports = {
+    report_error
+}
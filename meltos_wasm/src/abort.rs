@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_futures::js_sys;
+
+#[wasm_bindgen]
+extern {
+    #[derive(Debug, Clone)]
+    pub type AbortSignal;
+
+    #[wasm_bindgen(method, getter)]
+    pub fn aborted(this: &AbortSignal) -> bool;
+}
+
+
+/// The error an aborted operation rejects with, whose `name` is `AbortError` as in the DOM.
+pub fn abort_error() -> JsValue {
+    let error = js_sys::Error::new("the operation was aborted");
+    error.set_name("AbortError");
+    error.into()
+}
+
+
+#[inline]
+pub fn aborted_io_error() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Interrupted, "the operation was aborted")
+}
+
+
+/// Records the files overwritten or deleted by an abortable operation,
+/// so that they can be restored if it is aborted.
+#[derive(Debug)]
+pub struct Transaction {
+    pub signal: AbortSignal,
+    /// The original content of each file in the order first touched, `None` if it didn't exist.
+    journal: Vec<(String, Option<Vec<u8>>)>,
+    journaled: HashSet<String>,
+    /// The directories created, in the order created.
+    dirs: Vec<String>,
+}
+
+
+impl Transaction {
+    #[inline]
+    pub fn new(signal: AbortSignal) -> Self {
+        Self {
+            signal,
+            journal: Vec::new(),
+            journaled: HashSet::new(),
+            dirs: Vec::new(),
+        }
+    }
+
+
+    #[inline]
+    pub fn is_journaled(&self, path: &str) -> bool {
+        self.journaled.contains(path)
+    }
+
+
+    pub fn record(&mut self, path: String, original: Option<Vec<u8>>) {
+        if self.journaled.insert(path.clone()) {
+            self.journal.push((path, original));
+        }
+    }
+
+
+    #[inline]
+    pub fn record_dir(&mut self, path: String) {
+        self.dirs.push(path);
+    }
+
+
+    /// Takes the journal and the directories created, leaving them empty.
+    #[inline]
+    pub fn take(&mut self) -> (Vec<(String, Option<Vec<u8>>)>, Vec<String>) {
+        self.journaled.clear();
+        (std::mem::take(&mut self.journal), std::mem::take(&mut self.dirs))
+    }
+}
@@ -1,7 +1,217 @@
+use std::fmt::{Debug, Display};
+
+use serde::Serialize;
 use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_futures::js_sys;
 
 pub type Result<T = ()> = std::result::Result<T, JsValue>;
 
+pub const UNKNOWN: &str = "Unknown";
+pub const NOT_FOUND: &str = "NotFound";
+pub const NOTHING_TO_COMMIT: &str = "NothingToCommit";
+pub const PUSH_REJECTED: &str = "PushRejected";
+pub const CONFLICT: &str = "Conflict";
+pub const UNAUTHORIZED: &str = "Unauthorized";
+pub const ROOM_CLOSED: &str = "RoomClosed";
+pub const NETWORK: &str = "Network";
+pub const IO: &str = "Io";
+pub const CORRUPTED: &str = "Corrupted";
+/// The arguments or the current state of the repository don't allow the operation.
+pub const INVALID_OPERATION: &str = "InvalidOperation";
+
+
+#[wasm_bindgen(module = "/js/errors.js")]
+extern {
+    #[wasm_bindgen(js_name = create_error)]
+    fn _create_error(kind: &str, message: &str, details: JsValue, cause: JsValue) -> JsValue;
+
+    #[wasm_bindgen(js_name = error_classes)]
+    fn _error_classes() -> JsValue;
+}
+
+
+/// Returns the error classes keyed by their kind, such as `NotFound`,
+/// so that errors can be checked with `instanceof` as well as by `kind`.
+///
+/// All of them extend `TvcError`, which is keyed by `Unknown`.
+#[wasm_bindgen]
+pub fn error_classes() -> JsValue {
+    _error_classes()
+}
+
+
+/// An error thrown to javascript as an instance of the error class of its kind,
+/// with `kind`, `details` and the `cause` which it was converted from.
+#[derive(Debug)]
+pub struct TvcError {
+    kind: &'static str,
+    message: String,
+    details: JsValue,
+    cause: JsValue,
+}
+
+
+impl TvcError {
+    #[inline]
+    pub fn new(kind: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            details: JsValue::UNDEFINED,
+            cause: JsValue::UNDEFINED,
+        }
+    }
+
+
+    /// The `target` such as `branch` or `file` named `name` doesn't exist.
+    pub fn not_found(target: &str, name: impl Display) -> Self {
+        let name = name.to_string();
+        Self::new(NOT_FOUND, format!("not found {target}: {name}")).details(&NotFoundDetails {
+            target,
+            name: &name,
+        })
+    }
+
+
+    /// The changes to `paths` conflict with each other or with the operation.
+    pub fn conflict(message: impl Into<String>, paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        let paths: Vec<String> = paths.into_iter().map(Into::into).collect();
+        Self::new(CONFLICT, message).details(&ConflictDetails {
+            paths,
+        })
+    }
+
+
+    #[inline]
+    pub fn details(mut self, details: &impl Serialize) -> Self {
+        self.details = serde_wasm_bindgen::to_value(details).unwrap_or(JsValue::UNDEFINED);
+        self
+    }
+
+
+    /// Keeps the error raised by tvc, by the client or by the file system as `cause`,
+    /// an `Error` whose message is its debug representation.
+    #[inline]
+    pub fn cause(mut self, source: &impl Debug) -> Self {
+        self.cause = js_sys::Error::new(&format!("{source:?}")).into();
+        self
+    }
+}
+
+
+impl From<TvcError> for JsValue {
+    fn from(error: TvcError) -> Self {
+        _create_error(error.kind, &error.message, error.details, error.cause)
+    }
+}
+
+
+#[derive(Serialize)]
+struct NotFoundDetails<'a> {
+    target: &'a str,
+    name: &'a str,
+}
+
+
+#[derive(Serialize)]
+struct ConflictDetails {
+    paths: Vec<String>,
+}
+
+
+/// The kind of an error, such as `NotFound`, decided by its type.
+pub trait ErrorKind {
+    fn error_kind(&self) -> &'static str;
+}
+
+
+impl ErrorKind for std::io::Error {
+    fn error_kind(&self) -> &'static str {
+        match self.kind() {
+            std::io::ErrorKind::NotFound => NOT_FOUND,
+            // The errors of the node file system reach here only as their messages.
+            std::io::ErrorKind::Other => match kind_of_js_message(&self.to_string()) {
+                UNKNOWN => IO,
+                kind => kind,
+            },
+            _ => IO,
+        }
+    }
+}
+
+
+impl ErrorKind for meltos_tvc::error::Error {
+    fn error_kind(&self) -> &'static str {
+        use meltos_tvc::error::Error;
+        match self {
+            Error::NotfoundHead { .. }
+            | Error::NotfoundObj { .. }
+            | Error::NotfoundTrace { .. }
+            | Error::NotfoundBranch { .. }
+            | Error::NotfoundWorkspaceFile { .. } => NOT_FOUND,
+            Error::NotfoundStages { .. } => NOTHING_TO_COMMIT,
+            Error::Io(e) => e.error_kind(),
+            Error::SerdeJson { .. } => CORRUPTED,
+            _ => UNKNOWN,
+        }
+    }
+}
+
+
+impl ErrorKind for meltos_client::error::Error {
+    fn error_kind(&self) -> &'static str {
+        use meltos_client::error::Error;
+        match self {
+            Error::Tvc(e) => e.error_kind(),
+            Error::Reqwest(e) => match e.status().map(|status| status.as_u16()) {
+                Some(401 | 403) => UNAUTHORIZED,
+                Some(404 | 410) => ROOM_CLOSED,
+                Some(409) => PUSH_REJECTED,
+                Some(_) => UNKNOWN,
+                None => NETWORK,
+            },
+            _ => UNKNOWN,
+        }
+    }
+}
+
+
+/// Converts an error raised by tvc or by the client into the error class of its kind.
+#[inline]
+pub fn classify(error: impl ErrorKind + Display + Debug) -> JsValue {
+    classify_or(error, UNKNOWN)
+}
+
+
+/// Same as [`classify`], but the errors of unknown kind are given `fallback`,
+/// such as `PushRejected` for the errors the room returns for a push.
+pub fn classify_or(error: impl ErrorKind + Display + Debug, fallback: &'static str) -> JsValue {
+    let kind = match error.error_kind() {
+        UNKNOWN => fallback,
+        kind => kind,
+    };
+    TvcError::new(kind, error.to_string()).cause(&error).into()
+}
+
+
+/// Guesses the kind from the message of an error thrown by javascript, which has no type to match.
+fn kind_of_js_message(message: &str) -> &'static str {
+    let message = message.to_lowercase();
+    let contains = |keywords: &[&str]| keywords.iter().any(|keyword| message.contains(keyword));
+
+    if contains(&["no such file", "enoent"]) {
+        NOT_FOUND
+    } else if contains(&["failed to fetch", "network", "etimedout", "econnrefused", "econnreset", "enotfound"]) {
+        NETWORK
+    } else if contains(&["permission denied", "eacces", "eperm", "eexist", "enotdir", "eisdir", "enotempty"]) {
+        IO
+    } else {
+        UNKNOWN
+    }
+}
+
+
 pub trait IntoJsResult<T> {
     fn into_js_result(self) -> Result<T>;
 }
@@ -9,9 +219,75 @@ pub trait IntoJsResult<T> {
 
 impl<T> IntoJsResult<T> for std::io::Result<T> {
     fn into_js_result(self) -> Result<T> {
-        match self {
-            Ok(out) => Ok(out),
-            Err(e) => Err(JsValue::from_str(&e.to_string())),
-        }
+        self.map_err(|e| {
+            TvcError::new(e.error_kind(), e.to_string())
+                .details(&IoDetails {
+                    code: format!("{:?}", e.kind()),
+                })
+                .cause(&e)
+                .into()
+        })
+    }
+}
+
+
+impl<T> IntoJsResult<T> for meltos_tvc::error::Result<T> {
+    fn into_js_result(self) -> Result<T> {
+        self.map_err(classify)
+    }
+}
+
+
+#[derive(Serialize)]
+struct IoDetails {
+    code: String,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::js_sys::{Error, Reflect};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::error::{
+        classify, ErrorKind, INVALID_OPERATION, IntoJsResult, IO, kind_of_js_message, NETWORK, NOT_FOUND, TvcError,
+        UNKNOWN,
+    };
+
+    fn cause(error: &JsValue) -> JsValue {
+        Reflect::get(error, &"cause".into()).unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn kind_from_js_message() {
+        assert_eq!(kind_of_js_message("ENOENT: no such file or directory"), NOT_FOUND);
+        assert_eq!(kind_of_js_message("failed to fetch"), NETWORK);
+        assert_eq!(kind_of_js_message("EACCES: permission denied"), IO);
+        assert_eq!(kind_of_js_message("room not found: 1234"), UNKNOWN);
+    }
+
+    #[wasm_bindgen_test]
+    fn kind_from_type() {
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(not_found.error_kind(), NOT_FOUND);
+        assert_eq!(meltos_tvc::error::Error::Io(not_found).error_kind(), NOT_FOUND);
+        assert_eq!(std::io::Error::other("failed read file: ENOENT").error_kind(), NOT_FOUND);
+        assert_eq!(std::io::Error::other("something went wrong").error_kind(), IO);
+    }
+
+    #[wasm_bindgen_test]
+    fn keep_source_as_cause() {
+        let io_error = std::io::Result::<()>::Err(std::io::Error::other("disk is full")).into_js_result().unwrap_err();
+        let io_cause = cause(&io_error).dyn_into::<Error>().unwrap();
+        assert!(String::from(io_cause.message()).contains("disk is full"));
+
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let tvc_error = classify(meltos_tvc::error::Error::Io(not_found));
+        assert!(cause(&tvc_error).is_instance_of::<Error>());
+
+        let error: JsValue = TvcError::new(INVALID_OPERATION, "no source").into();
+        assert!(cause(&error).is_undefined());
     }
 }
@@ -1,4 +1,6 @@
+use std::cell::RefCell;
 use std::path::Path;
+use std::rc::Rc;
 
 use async_trait::async_trait;
 use meltos_tvc::file_system::{FileSystem, Stat};
@@ -6,13 +8,20 @@ use meltos_tvc::file_system::memory::MemoryFileSystem;
 use meltos_util::path::AsUri;
 use wasm_bindgen::prelude::wasm_bindgen;
 
+use crate::abort::{aborted_io_error, AbortSignal, Transaction};
 use crate::error;
 use crate::error::IntoJsResult;
 use crate::file_system::node::NodeFileSystem;
+use crate::file_system::path::normalize_workspace_path;
 use crate::js_vec::{JsVecString, JsVecU8};
 use crate::vscode::{CHANGE, CREATE, DELETE, FileChangeEventEmitter};
 
+pub mod diff;
+mod ignore;
+pub mod list;
 pub mod node;
+pub mod path;
+mod transfer;
 pub mod vscode_node;
 
 
@@ -22,6 +31,9 @@ pub struct WasmFileSystem {
     repository: NodeFileSystem,
     workspace: MemoryFileSystem,
     emitter: Option<FileChangeEventEmitter>,
+    /// Journals the writes made through this handle and its clones, so that they can be rolled back.
+    /// Only the handle passed to an abortable operation has one.
+    transaction: Option<Rc<RefCell<Transaction>>>,
 }
 
 
@@ -35,6 +47,7 @@ impl WasmFileSystem {
             emitter,
             repository: NodeFileSystem::default(),
             workspace: MemoryFileSystem::default(),
+            transaction: None,
         }
     }
 
@@ -92,14 +105,19 @@ impl WasmFileSystem {
 impl FileSystem for WasmFileSystem {
     #[inline(always)]
     async fn stat(&self, path: &str) -> std::io::Result<Option<Stat>> {
+        let path = &normalize_workspace_path(path);
         self.fs(path).stat(path).await
     }
 
     #[inline(always)]
     async fn write_file(&self, path: &str, buf: &[u8]) -> std::io::Result<()> {
+        let path = &normalize_workspace_path(path);
+        self.check_aborted()?;
+        self.journal(path).await?;
         let fs = self.fs(path);
         if let Some(parent) = Path::new(path).parent().map(|path| path.as_uri()) {
             if fs.read_dir(&parent).await?.is_none() {
+                self.journal_dir(&parent);
                 fs.create_dir(&parent).await?;
                 self.notify(&parent, CREATE);
             }
@@ -113,7 +131,12 @@ impl FileSystem for WasmFileSystem {
 
     #[inline(always)]
     async fn create_dir(&self, path: &str) -> std::io::Result<()> {
+        let path = &normalize_workspace_path(path);
+        self.check_aborted()?;
         let exists = self.exists(path).await?;
+        if !exists {
+            self.journal_dir(path);
+        }
         self.fs(path).create_dir(path).await?;
         self.notify(path, if exists { CHANGE } else { CREATE });
         Ok(())
@@ -121,11 +144,14 @@ impl FileSystem for WasmFileSystem {
 
     #[inline(always)]
     async fn read_file(&self, path: &str) -> std::io::Result<Option<Vec<u8>>> {
+        let path = &normalize_workspace_path(path);
+        self.check_aborted()?;
         self.fs(path).read_file(path).await
     }
 
     #[inline(always)]
     async fn read_dir(&self, path: &str) -> std::io::Result<Option<Vec<String>>> {
+        let path = &normalize_workspace_path(path);
         if path == "." {
             let entries = self.workspace.read_dir(".").await?;
             let entries2 = self.repository.read_dir(".").await?;
@@ -143,10 +169,19 @@ impl FileSystem for WasmFileSystem {
 
     #[inline(always)]
     async fn delete(&self, path: &str) -> std::io::Result<()> {
+        let path = &normalize_workspace_path(path);
         if path == "." {
             self.repository.delete(".").await?;
             self.workspace.delete(".").await?;
         } else {
+            self.check_aborted()?;
+            if self.fs(path).read_file(path).await?.is_some() {
+                self.journal(path).await?;
+            } else {
+                for file in self.fs(path).all_files_in(path).await? {
+                    self.journal(&file).await?;
+                }
+            }
             self.fs(path).delete(path).await?;
         }
 
@@ -155,6 +190,7 @@ impl FileSystem for WasmFileSystem {
     }
 
     async fn all_files_in(&self, path: &str) -> std::io::Result<Vec<String>> {
+        let path = &normalize_workspace_path(path);
         if path == "." {
             let mut files = self.repository.all_files_in(".").await?;
             files.extend(self.workspace.all_files_in(".").await?);
@@ -167,8 +203,17 @@ impl FileSystem for WasmFileSystem {
 
 
 impl WasmFileSystem {
+    #[cfg(test)]
+    pub(crate) fn with_repository(repository: NodeFileSystem) -> Self {
+        Self {
+            repository,
+            ..Self::new(None)
+        }
+    }
+
+
     fn fs(&self, path: &str) -> &dyn FileSystem {
-        if path.starts_with("workspace") || path.starts_with("/workspace") {
+        if is_workspace_path(path) {
             &self.workspace
         } else {
             &self.repository
@@ -181,6 +226,78 @@ impl WasmFileSystem {
     }
 
 
+    /// Creates a handle whose writes are journaled until [`WasmFileSystem::rollback`],
+    /// and whose accesses fail once `signal` is aborted.
+    ///
+    /// The writes made through the other handles, such as the user's edits, aren't affected.
+    pub(crate) fn transactional(&self, signal: AbortSignal) -> Self {
+        Self {
+            transaction: Some(Rc::new(RefCell::new(Transaction::new(signal)))),
+            ..self.clone()
+        }
+    }
+
+
+    /// Restores the files written or deleted and removes the directories created through this handle.
+    pub(crate) async fn rollback(&self) -> std::io::Result<()> {
+        let Some(transaction) = self.transaction.as_ref() else {
+            return Ok(());
+        };
+        let (journal, dirs) = transaction.borrow_mut().take();
+        let fs = Self {
+            transaction: None,
+            ..self.clone()
+        };
+        for (path, original) in journal.into_iter().rev() {
+            match original {
+                Some(buf) => fs.write_file(&path, &buf).await?,
+                None => {
+                    if fs.fs(&path).read_file(&path).await?.is_some() {
+                        fs.delete(&path).await?;
+                    }
+                }
+            }
+        }
+        for dir in dirs.into_iter().rev() {
+            if fs.read_dir(&dir).await?.is_some_and(|entries| entries.is_empty()) {
+                fs.delete(&dir).await?;
+            }
+        }
+        Ok(())
+    }
+
+
+    #[inline]
+    fn check_aborted(&self) -> std::io::Result<()> {
+        match self.transaction.as_ref() {
+            Some(transaction) if transaction.borrow().signal.aborted() => Err(aborted_io_error()),
+            _ => Ok(()),
+        }
+    }
+
+
+    /// Saves the original content of the file before the transaction first changes it.
+    async fn journal(&self, path: &str) -> std::io::Result<()> {
+        let Some(transaction) = self.transaction.as_ref() else {
+            return Ok(());
+        };
+        if transaction.borrow().is_journaled(path) {
+            return Ok(());
+        }
+        let original = self.fs(path).read_file(path).await?;
+        transaction.borrow_mut().record(path.to_string(), original);
+        Ok(())
+    }
+
+
+    #[inline]
+    fn journal_dir(&self, path: &str) {
+        if let Some(transaction) = self.transaction.as_ref() {
+            transaction.borrow_mut().record_dir(path.to_string());
+        }
+    }
+
+
     fn notify(&self, uri: &str, change_type: &str) {
         // if !(uri.starts_with("workspace") || uri.starts_with("/workspace")) {
         //     return;
@@ -190,3 +307,9 @@ impl WasmFileSystem {
         }
     }
 }
+
+
+#[inline(always)]
+pub(crate) fn is_workspace_path(path: &str) -> bool {
+    path.starts_with("workspace") || path.starts_with("/workspace")
+}
@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use meltos_tvc::file_system::{FileSystem, Stat};
+use meltos_tvc::object::ObjHash;
+use meltos_util::path::AsUri;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::IntoJsResult;
+use crate::file_system::WasmFileSystem;
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TreeDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<String>,
+    pub directories: Vec<DirDiffSummary>,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DirDiffSummary {
+    pub dir: String,
+    pub added: u32,
+    pub removed: u32,
+    pub modified: u32,
+}
+
+
+#[wasm_bindgen]
+impl WasmFileSystem {
+    /// Compares all files under `left` with all files under `right`.
+    ///
+    /// Paths in the result are relative to each root.
+    /// Files are compared by size and update time unless `compare_content` is true,
+    /// in which case the hashes of their contents are compared.
+    pub async fn diff_trees_api(
+        &self,
+        left: &str,
+        right: &str,
+        compare_content: Option<bool>,
+    ) -> error::Result<TreeDiff> {
+        self
+            .diff_trees(left, right, compare_content.unwrap_or(false))
+            .await
+            .into_js_result()
+    }
+}
+
+
+impl WasmFileSystem {
+    pub async fn diff_trees(
+        &self,
+        left: &str,
+        right: &str,
+        compare_content: bool,
+    ) -> std::io::Result<TreeDiff> {
+        let left_files = self.relative_files_in(left).await?;
+        let right_files = self.relative_files_in(right).await?;
+
+        let mut diff = TreeDiff::default();
+        for file in left_files.difference(&right_files) {
+            diff.removed.push(file.clone());
+        }
+        for file in right_files.difference(&left_files) {
+            diff.added.push(file.clone());
+        }
+        for file in left_files.intersection(&right_files) {
+            let left_path = join(left, file);
+            let right_path = join(right, file);
+            let changed = if compare_content {
+                self.content_hash(&left_path).await? != self.content_hash(&right_path).await?
+            } else {
+                is_stat_changed(
+                    self.stat(&left_path).await?,
+                    self.stat(&right_path).await?,
+                )
+            };
+            if changed {
+                diff.modified.push(file.clone());
+            }
+        }
+
+        diff.directories = summarize_dirs(&diff);
+        Ok(diff)
+    }
+
+
+    async fn relative_files_in(&self, root: &str) -> std::io::Result<BTreeSet<String>> {
+        let root = trim_root(root);
+        let prefix = format!("{}/", root.trim_start_matches('/'));
+        Ok(self
+            .all_files_in(root)
+            .await?
+            .into_iter()
+            .map(|file| {
+                let file = file.trim_start_matches('/');
+                if root == "." {
+                    file.to_string()
+                } else {
+                    file.strip_prefix(&prefix).unwrap_or(file).to_string()
+                }
+            })
+            .collect())
+    }
+
+
+    async fn content_hash(&self, path: &str) -> std::io::Result<Option<ObjHash>> {
+        Ok(self
+            .read_file(path)
+            .await?
+            .map(|buf| ObjHash::new(&buf)))
+    }
+}
+
+
+fn is_stat_changed(left: Option<Stat>, right: Option<Stat>) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => left.size != right.size || left.update_time != right.update_time,
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+
+fn summarize_dirs(diff: &TreeDiff) -> Vec<DirDiffSummary> {
+    let mut summaries = BTreeMap::<String, DirDiffSummary>::new();
+    for file in &diff.added {
+        dir_summary(&mut summaries, file).added += 1;
+    }
+    for file in &diff.removed {
+        dir_summary(&mut summaries, file).removed += 1;
+    }
+    for file in &diff.modified {
+        dir_summary(&mut summaries, file).modified += 1;
+    }
+    summaries.into_values().collect()
+}
+
+
+fn dir_summary<'a>(
+    summaries: &'a mut BTreeMap<String, DirDiffSummary>,
+    file: &str,
+) -> &'a mut DirDiffSummary {
+    let dir = parent_dir(file);
+    summaries
+        .entry(dir.clone())
+        .or_insert_with(|| DirDiffSummary {
+            dir,
+            ..DirDiffSummary::default()
+        })
+}
+
+
+#[inline]
+fn parent_dir(file: &str) -> String {
+    Path::new(file)
+        .parent()
+        .map(|dir| dir.as_uri())
+        .filter(|dir| !dir.is_empty())
+        .unwrap_or_else(|| ".".to_string())
+}
+
+
+#[inline]
+fn trim_root(root: &str) -> &str {
+    let root = root.trim_end_matches('/');
+    if root.is_empty() || root == "." {
+        "."
+    } else {
+        root.trim_start_matches("./")
+    }
+}
+
+
+#[inline]
+fn join(root: &str, file: &str) -> String {
+    let root = trim_root(root);
+    if root == "." {
+        file.to_string()
+    } else {
+        format!("{root}/{file}")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::file_system::WasmFileSystem;
+
+    #[wasm_bindgen_test]
+    async fn detect_added_removed_and_modified() {
+        let fs = WasmFileSystem::new(None);
+        fs.write_file("workspace/left/same.txt", b"same").await.unwrap();
+        fs.write_file("workspace/left/removed.txt", b"removed").await.unwrap();
+        fs.write_file("workspace/left/src/modified.txt", b"before").await.unwrap();
+        fs.write_file("workspace/right/same.txt", b"same").await.unwrap();
+        fs.write_file("workspace/right/src/modified.txt", b"after").await.unwrap();
+        fs.write_file("workspace/right/src/added.txt", b"added").await.unwrap();
+
+        let diff = fs.diff_trees("workspace/left", "workspace/right", true).await.unwrap();
+        assert_eq!(diff.added, vec!["src/added.txt".to_string()]);
+        assert_eq!(diff.removed, vec!["removed.txt".to_string()]);
+        assert_eq!(diff.modified, vec!["src/modified.txt".to_string()]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn summarize_per_directory() {
+        let fs = WasmFileSystem::new(None);
+        fs.write_file("workspace/left2/src/a.txt", b"a").await.unwrap();
+        fs.write_file("workspace/right2/src/b.txt", b"b").await.unwrap();
+        fs.write_file("workspace/right2/c.txt", b"c").await.unwrap();
+
+        let diff = fs.diff_trees("workspace/left2", "workspace/right2", true).await.unwrap();
+        let src = diff.directories.iter().find(|d| d.dir == "src").unwrap();
+        assert_eq!((src.added, src.removed, src.modified), (1, 1, 0));
+        let root = diff.directories.iter().find(|d| d.dir == ".").unwrap();
+        assert_eq!((root.added, root.removed, root.modified), (1, 0, 0));
+    }
+}
@@ -0,0 +1,96 @@
+use glob::Pattern;
+use meltos_tvc::file_system::FileSystem;
+
+pub const IGNORE_FILE: &str = ".meltosignore";
+
+const ALWAYS_IGNORED: &[&str] = &[".meltos", ".git"];
+
+
+/// Ignore rules read from `.meltosignore`.
+///
+/// Each non-empty line that is not a comment is a glob pattern.
+/// A pattern containing `/` is matched against the relative path,
+/// otherwise it is matched against each path segment.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    patterns: Vec<Pattern>,
+}
+
+
+impl IgnoreRules {
+    pub async fn read(fs: &impl FileSystem, root: &str) -> std::io::Result<Self> {
+        let path = if root == "." {
+            IGNORE_FILE.to_string()
+        } else {
+            format!("{}/{IGNORE_FILE}", root.trim_end_matches('/'))
+        };
+        let Some(buf) = fs.read_file(&path).await? else {
+            return Ok(Self::default());
+        };
+        Ok(Self::parse(&String::from_utf8_lossy(&buf)))
+    }
+
+
+    pub fn parse(rules: &str) -> Self {
+        let patterns = rules
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| Pattern::new(line.trim_end_matches('/')).ok())
+            .collect();
+        Self {
+            patterns
+        }
+    }
+
+
+    pub fn is_ignored(&self, relative_path: &str) -> bool {
+        let relative_path = relative_path.trim_start_matches("./").trim_start_matches('/');
+        let mut segments = relative_path.split('/');
+        if segments.any(|segment| ALWAYS_IGNORED.contains(&segment)) {
+            return true;
+        }
+
+        self.patterns.iter().any(|pattern| {
+            if pattern.as_str().contains('/') {
+                ancestors(relative_path).any(|path| pattern.matches(path))
+            } else {
+                relative_path.split('/').any(|segment| pattern.matches(segment))
+            }
+        })
+    }
+}
+
+
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    path
+        .match_indices('/')
+        .map(|(i, _)| &path[..i])
+        .chain(std::iter::once(path))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::file_system::ignore::IgnoreRules;
+
+    #[wasm_bindgen_test]
+    fn always_ignore_meltos_dir() {
+        let rules = IgnoreRules::default();
+        assert!(rules.is_ignored(".meltos/objects/abc"));
+        assert!(!rules.is_ignored("src/hello.txt"));
+    }
+
+    #[wasm_bindgen_test]
+    fn ignore_by_segment_and_path() {
+        let rules = IgnoreRules::parse("# comment\nnode_modules/\n*.log\nsrc/gen\n");
+        assert!(rules.is_ignored("node_modules/a/index.js"));
+        assert!(rules.is_ignored("web/node_modules/index.js"));
+        assert!(rules.is_ignored("debug.log"));
+        assert!(rules.is_ignored("src/gen/out.rs"));
+        assert!(!rules.is_ignored("gen/out.rs"));
+        assert!(!rules.is_ignored("src/main.rs"));
+    }
+}
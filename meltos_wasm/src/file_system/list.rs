@@ -0,0 +1,197 @@
+use std::path::Path;
+
+use glob::Pattern;
+use meltos_tvc::file_system::FileSystem;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{IntoJsResult, INVALID_OPERATION, TvcError};
+use crate::file_system::WasmFileSystem;
+
+const DEFAULT_LIMIT: u32 = 1000;
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ReadDirPage {
+    pub entries: Vec<String>,
+    /// Pass this to the next call to continue listing, `undefined` if there are no more entries.
+    pub next_cursor: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EntryType {
+    File,
+    Dir,
+}
+
+
+impl EntryType {
+    fn parse(entry_type: &str) -> error::Result<Self> {
+        match entry_type {
+            "file" => Ok(Self::File),
+            "dir" => Ok(Self::Dir),
+            _ => Err(TvcError::new(INVALID_OPERATION, format!("unknown entry type: {entry_type}")).into()),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct ReadDirFilter {
+    pub pattern: Option<Pattern>,
+    pub entry_type: Option<EntryType>,
+}
+
+
+#[wasm_bindgen]
+impl WasmFileSystem {
+    /// Reads the entries of the directory in sorted order, at most `limit` entries at a time.
+    ///
+    /// `limit` must be at least 1. `pattern` is a glob matched against each entry name and
+    /// `entry_type` is either `file` or `dir`.
+    pub async fn read_dir_page_api(
+        &self,
+        path: &str,
+        cursor: Option<String>,
+        limit: Option<u32>,
+        pattern: Option<String>,
+        entry_type: Option<String>,
+    ) -> error::Result<Option<ReadDirPage>> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT);
+        if limit == 0 {
+            return Err(TvcError::new(INVALID_OPERATION, "limit must be at least 1").into());
+        }
+        let filter = ReadDirFilter {
+            pattern: pattern
+                .map(|pattern| Pattern::new(&pattern))
+                .transpose()
+                .map_err(|e| TvcError::new(INVALID_OPERATION, e.to_string()))?,
+            entry_type: entry_type
+                .map(|entry_type| EntryType::parse(&entry_type))
+                .transpose()?,
+        };
+        self
+            .read_dir_page(path, cursor.as_deref(), limit, &filter)
+            .await
+            .into_js_result()
+    }
+}
+
+
+impl WasmFileSystem {
+    pub async fn read_dir_page(
+        &self,
+        path: &str,
+        cursor: Option<&str>,
+        limit: u32,
+        filter: &ReadDirFilter,
+    ) -> std::io::Result<Option<ReadDirPage>> {
+        let Some(mut entries) = self.read_dir(path).await? else {
+            return Ok(None);
+        };
+        entries.sort();
+        entries.dedup();
+
+        let start = cursor
+            .map(|cursor| entries.partition_point(|entry| entry.as_str() <= cursor))
+            .unwrap_or(0);
+        let mut page = ReadDirPage::default();
+        for entry in &entries[start..] {
+            if page.entries.len() == limit as usize {
+                page.next_cursor = page.entries.last().cloned();
+                break;
+            }
+            if self.matches(entry, filter).await? {
+                page.entries.push(entry.clone());
+            }
+        }
+        Ok(Some(page))
+    }
+
+
+    async fn matches(&self, entry: &str, filter: &ReadDirFilter) -> std::io::Result<bool> {
+        if let Some(pattern) = filter.pattern.as_ref() {
+            let name = Path::new(entry)
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(entry);
+            if !pattern.matches(name) {
+                return Ok(false);
+            }
+        }
+
+        let Some(entry_type) = filter.entry_type else {
+            return Ok(true);
+        };
+        let Some(stat) = self.stat(entry).await? else {
+            return Ok(false);
+        };
+        Ok(match entry_type {
+            EntryType::File => stat.is_file(),
+            EntryType::Dir => stat.is_dir(),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use glob::Pattern;
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::file_system::list::{EntryType, ReadDirFilter};
+    use crate::file_system::WasmFileSystem;
+
+    #[wasm_bindgen_test]
+    async fn paginate_in_sorted_order() {
+        let fs = WasmFileSystem::new(None);
+        for name in ["c.txt", "a.txt", "b.txt"] {
+            fs.write_file(&format!("workspace/page1/{name}"), b"1").await.unwrap();
+        }
+
+        let filter = ReadDirFilter::default();
+        let page1 = fs.read_dir_page("workspace/page1", None, 2, &filter).await.unwrap().unwrap();
+        assert_eq!(page1.entries, vec![
+            "workspace/page1/a.txt".to_string(),
+            "workspace/page1/b.txt".to_string(),
+        ]);
+
+        let cursor = page1.next_cursor.unwrap();
+        let page2 = fs.read_dir_page("workspace/page1", Some(&cursor), 2, &filter).await.unwrap().unwrap();
+        assert_eq!(page2.entries, vec!["workspace/page1/c.txt".to_string()]);
+        assert_eq!(page2.next_cursor, None);
+    }
+
+    #[wasm_bindgen_test]
+    async fn reject_zero_limit() {
+        let fs = WasmFileSystem::new(None);
+        fs.write_file("workspace/zero_limit/a.txt", b"1").await.unwrap();
+        assert!(fs.read_dir_page_api("workspace/zero_limit", None, Some(0), None, None).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn filter_by_pattern_and_entry_type() {
+        let fs = WasmFileSystem::new(None);
+        fs.write_file("workspace/page2/a.rs", b"1").await.unwrap();
+        fs.write_file("workspace/page2/b.txt", b"1").await.unwrap();
+        fs.write_file("workspace/page2/src/c.rs", b"1").await.unwrap();
+
+        let filter = ReadDirFilter {
+            pattern: Some(Pattern::new("*.rs").unwrap()),
+            entry_type: None,
+        };
+        let page = fs.read_dir_page("workspace/page2", None, 10, &filter).await.unwrap().unwrap();
+        assert_eq!(page.entries, vec!["workspace/page2/a.rs".to_string()]);
+
+        let filter = ReadDirFilter {
+            pattern: None,
+            entry_type: Some(EntryType::Dir),
+        };
+        let page = fs.read_dir_page("workspace/page2", None, 10, &filter).await.unwrap().unwrap();
+        assert_eq!(page.entries, vec!["workspace/page2/src".to_string()]);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::file_system::is_workspace_path;
+
+/// Normalizes `path` into the Unicode NFC form.
+///
+/// The paths of the workspace and the keys of trees are normalized,
+/// so the NFC and NFD forms of a name always refer to the same entry.
+#[inline]
+pub fn normalize_path(path: &str) -> String {
+    path.nfc().collect()
+}
+
+
+/// Normalizes `path` only if it is in the workspace.
+///
+/// The paths on the disk are left as they are,
+/// since a file named in the NFD form can't be reached by its NFC form on a non-normalizing disk.
+#[inline]
+pub fn normalize_workspace_path(path: &str) -> String {
+    if is_workspace_path(path) {
+        normalize_path(path)
+    } else {
+        path.to_string()
+    }
+}
+
+
+/// The key two paths share when they clobber each other
+/// on a case-insensitive or normalizing file system.
+#[inline]
+pub fn collision_key(path: &str) -> String {
+    path.nfc().flat_map(char::to_lowercase).collect()
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PathCollision {
+    pub paths: Vec<String>,
+}
+
+
+/// Returns the groups of distinct paths which share the same [`collision_key`].
+pub fn find_collisions<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<PathCollision> {
+    let mut groups = BTreeMap::<String, Vec<String>>::new();
+    for path in paths {
+        let group = groups.entry(collision_key(path)).or_default();
+        if !group.iter().any(|p| p == path) {
+            group.push(path.to_string());
+        }
+    }
+
+    groups
+        .into_values()
+        .filter(|paths| 1 < paths.len())
+        .map(|paths| PathCollision {
+            paths
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::file_system::path::{find_collisions, normalize_path, normalize_workspace_path};
+
+    #[wasm_bindgen_test]
+    fn normalize_nfd_into_nfc() {
+        assert_eq!(normalize_path("cafe\u{301}.txt"), "caf\u{e9}.txt");
+    }
+
+    #[wasm_bindgen_test]
+    fn keep_disk_path() {
+        assert_eq!(normalize_workspace_path("workspace/cafe\u{301}.txt"), "workspace/caf\u{e9}.txt");
+        assert_eq!(normalize_workspace_path("out/cafe\u{301}.txt"), "out/cafe\u{301}.txt");
+    }
+
+    #[wasm_bindgen_test]
+    fn detect_case_collision() {
+        let collisions = find_collisions(["Readme.md", "README.md", "src/main.rs"]);
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].paths, vec!["Readme.md".to_string(), "README.md".to_string()]);
+    }
+
+    #[wasm_bindgen_test]
+    fn detect_normalization_collision() {
+        let collisions = find_collisions(["caf\u{e9}.txt", "cafe\u{301}.txt"]);
+        assert_eq!(collisions.len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn same_path_is_not_collision() {
+        assert!(find_collisions(["a.txt", "a.txt"]).is_empty());
+    }
+}
@@ -0,0 +1,198 @@
+use meltos_tvc::file_system::FileSystem;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::IntoJsResult;
+use crate::file_system::ignore::IgnoreRules;
+use crate::file_system::{is_workspace_path, WasmFileSystem};
+use crate::js_vec::JsVecString;
+use crate::progress::{EXPORTING, IMPORTING, ProgressReporter, report};
+
+#[wasm_bindgen]
+impl WasmFileSystem {
+    /// Copies all files under `disk_path` into `workspace_path` of the memory workspace.
+    ///
+    /// Files matched by `.meltosignore` in `disk_path` are skipped.
+    /// Returns the workspace paths of the imported files.
+    pub async fn import_dir_api(
+        &self,
+        disk_path: &str,
+        workspace_path: &str,
+        progress: Option<ProgressReporter>,
+    ) -> error::Result<JsVecString> {
+        let files = self
+            .import_dir(disk_path, workspace_path, progress.as_ref())
+            .await
+            .into_js_result()?;
+        Ok(JsVecString(files))
+    }
+
+    /// Copies all files under `workspace_path` of the memory workspace into `disk_path`.
+    ///
+    /// Files matched by `.meltosignore` in `workspace_path` are skipped,
+    /// and the change of each exported file is notified as written by [`WasmFileSystem::write_file_api`].
+    /// Returns the disk paths of the exported files.
+    pub async fn export_dir_api(
+        &self,
+        workspace_path: &str,
+        disk_path: &str,
+        progress: Option<ProgressReporter>,
+    ) -> error::Result<JsVecString> {
+        let files = self
+            .export_dir(workspace_path, disk_path, progress.as_ref())
+            .await
+            .into_js_result()?;
+        Ok(JsVecString(files))
+    }
+}
+
+
+impl WasmFileSystem {
+    pub async fn import_dir(
+        &self,
+        disk_path: &str,
+        workspace_path: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> std::io::Result<Vec<String>> {
+        if !is_workspace_path(workspace_path) {
+            return Err(not_workspace_path(workspace_path));
+        }
+        if is_workspace_path(disk_path) {
+            return Err(not_disk_path(disk_path));
+        }
+        self.transfer(
+            &self.repository,
+            self,
+            disk_path,
+            workspace_path,
+            IMPORTING,
+            progress,
+        ).await
+    }
+
+
+    pub async fn export_dir(
+        &self,
+        workspace_path: &str,
+        disk_path: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> std::io::Result<Vec<String>> {
+        if !is_workspace_path(workspace_path) {
+            return Err(not_workspace_path(workspace_path));
+        }
+        if is_workspace_path(disk_path) {
+            return Err(not_disk_path(disk_path));
+        }
+        // Written through `self` rather than the disk directly so that the changes are notified.
+        self.transfer(
+            &self.workspace,
+            self,
+            workspace_path,
+            disk_path,
+            EXPORTING,
+            progress,
+        ).await
+    }
+
+
+    async fn transfer(
+        &self,
+        src_fs: &dyn FileSystem,
+        dist_fs: &dyn FileSystem,
+        src: &str,
+        dist: &str,
+        phase: &str,
+        progress: Option<&ProgressReporter>,
+    ) -> std::io::Result<Vec<String>> {
+        let src = src.trim_end_matches('/');
+        let dist = dist.trim_end_matches('/');
+        let ignore = IgnoreRules::read(src_fs, src).await?;
+        let files: Vec<(String, String)> = src_fs
+            .all_files_in(src)
+            .await?
+            .into_iter()
+            .filter_map(|file| {
+                let relative = file
+                    .strip_prefix(src)
+                    .unwrap_or(&file)
+                    .trim_start_matches('/')
+                    .to_string();
+                (!ignore.is_ignored(&relative)).then_some((file, relative))
+            })
+            .collect();
+
+        let total = files.len();
+        let mut transferred = Vec::with_capacity(total);
+        for (i, (src_file, relative)) in files.into_iter().enumerate() {
+            let dist_file = format!("{dist}/{relative}");
+            report(progress, phase, i, total, Some(&dist_file));
+            let Some(buf) = src_fs.read_file(&src_file).await? else {
+                continue;
+            };
+            dist_fs.write_file(&dist_file, &buf).await?;
+            transferred.push(dist_file);
+        }
+        report(progress, phase, total, total, None);
+        Ok(transferred)
+    }
+}
+
+
+#[inline]
+fn not_workspace_path(path: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{path} is not in the workspace"),
+    )
+}
+
+
+#[inline]
+fn not_disk_path(path: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{path} is in the workspace, not on the disk"),
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::wasm_fs;
+
+    #[wasm_bindgen_test]
+    async fn import_and_export_dir() {
+        let fs = wasm_fs("transfer_round_trip").await;
+        fs.repository.write_sync("src/hello.txt", b"hello").unwrap();
+        fs.repository.write_sync("src/dist/sample.txt", b"sample").unwrap();
+        fs.repository.write_sync("src/debug.log", b"log").unwrap();
+        fs.repository.write_sync("src/.meltosignore", b"*.log").unwrap();
+
+        let mut imported = fs.import_dir("src", "workspace/src", None).await.unwrap();
+        imported.sort();
+        assert_eq!(imported, vec![
+            "workspace/src/.meltosignore".to_string(),
+            "workspace/src/dist/sample.txt".to_string(),
+            "workspace/src/hello.txt".to_string(),
+        ]);
+
+        fs.export_dir("workspace/src", "out", None).await.unwrap();
+        assert_eq!(fs.repository.read_file("out/hello.txt").await.unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(fs.repository.read_file("out/dist/sample.txt").await.unwrap(), Some(b"sample".to_vec()));
+        assert_eq!(fs.repository.read_file("out/debug.log").await.unwrap(), None);
+    }
+
+    #[wasm_bindgen_test]
+    async fn reject_disk_path_in_workspace() {
+        let fs = wasm_fs("transfer_reject").await;
+        fs.write_file("workspace/src/hello.txt", b"hello").await.unwrap();
+
+        assert!(fs.export_dir("workspace/src", "workspace/out", None).await.is_err());
+        assert!(fs.import_dir("workspace/src", "workspace/dist", None).await.is_err());
+        assert_eq!(fs.read_file("workspace/out/hello.txt").await.unwrap(), None);
+        assert_eq!(fs.read_file("workspace/dist/hello.txt").await.unwrap(), None);
+    }
+}
@@ -3,6 +3,14 @@ use wasm_bindgen::prelude::wasm_bindgen;
 
 use meltos_client::tvc::BranchCommitMeta;
 
+use crate::file_system::path::PathCollision;
+use crate::tvc::blame::BlameLine;
+use crate::tvc::diff::FileDiff;
+use crate::tvc::rebase::RebaseStep;
+use crate::tvc::stash::StashEntry;
+use crate::tvc::status::StatusEntry;
+use crate::tvc::tag::Tag;
+
 
 #[wasm_bindgen(getter_with_clone)]
 #[repr(transparent)]
@@ -20,3 +28,44 @@ pub struct JsVecString(pub Vec<String>);
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct JsVecBranchCommitMeta(pub Vec<BranchCommitMeta>);
 
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecFileDiff(pub Vec<FileDiff>);
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecStatusEntry(pub Vec<StatusEntry>);
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecStashEntry(pub Vec<StashEntry>);
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecTag(pub Vec<Tag>);
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecBlameLine(pub Vec<BlameLine>);
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecRebaseStep(pub Vec<RebaseStep>);
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[repr(transparent)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JsVecPathCollision(pub Vec<PathCollision>);
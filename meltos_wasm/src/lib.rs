@@ -7,11 +7,18 @@ mod js_vec;
 mod vscode;
 mod directory;
 mod sleep;
+mod progress;
+mod abort;
 
 #[cfg(test)]
 pub mod tests {
+    use meltos_tvc::file_system::FileSystem;
+    use meltos_tvc::object::commit::CommitHash;
+
     use crate::directory::home_dir;
     use crate::file_system::node::NodeFileSystem;
+    use crate::file_system::WasmFileSystem;
+    use crate::tvc::WasmTvcClient;
 
     pub fn workspace_folder() -> String {
         format!("{}/tmp", home_dir())
@@ -20,4 +27,51 @@ pub mod tests {
     pub fn node_fs() -> NodeFileSystem {
         NodeFileSystem::new(workspace_folder())
     }
+
+    /// Creates a file system whose disk files and repository are in an empty directory named `name`,
+    /// so that the tests don't share their repositories.
+    pub async fn wasm_fs(name: &str) -> WasmFileSystem {
+        let repository = NodeFileSystem::new(format!("{}/{name}", workspace_folder()));
+        repository.delete(".").await.unwrap();
+        WasmFileSystem::with_repository(repository)
+    }
+
+    /// Creates a client over [`wasm_fs`] with the repository initialized on the branch `main`.
+    pub async fn tvc_client(name: &str) -> WasmTvcClient {
+        let client = WasmTvcClient::new(&wasm_fs(name).await);
+        client.init_repository("main".to_string()).await.unwrap();
+        client
+    }
+
+    /// Writes `(path, content)` of each file into the workspace.
+    pub async fn write_files(client: &WasmTvcClient, files: &[(&str, &str)]) {
+        for (path, content) in files {
+            client.fs().write_file(path, content.as_bytes()).await.unwrap();
+        }
+    }
+
+    /// Writes the files, then stages the whole workspace and commits it on the branch.
+    pub async fn commit_files(client: &WasmTvcClient, branch_name: &str, files: &[(&str, &str)]) -> CommitHash {
+        write_files(client, files).await;
+        client.stage(branch_name.to_string(), "workspace".to_string()).await.unwrap();
+        client.commit(branch_name.to_string(), "commit".to_string(), None).await.unwrap()
+    }
+
+    /// Creates a client in which `feature` has committed `theirs` and `main` has committed `ours`
+    /// on top of a commit of `base`, with `main` checked out.
+    pub async fn diverged_client(
+        name: &str,
+        base: &[(&str, &str)],
+        ours: &[(&str, &str)],
+        theirs: &[(&str, &str)],
+    ) -> WasmTvcClient {
+        let client = tvc_client(name).await;
+        commit_files(&client, "main", base).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        commit_files(&client, "feature", theirs).await;
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        commit_files(&client, "main", ours).await;
+        client
+    }
 }
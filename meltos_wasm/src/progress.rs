@@ -0,0 +1,99 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+
+#[wasm_bindgen]
+extern {
+    #[derive(Debug, Clone)]
+    pub type ProgressReporter;
+
+    #[wasm_bindgen(method)]
+    pub fn report(this: &ProgressReporter, progress: Progress);
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Progress {
+    pub phase: String,
+    pub current: u32,
+    /// 0 if the total isn't known in advance.
+    pub total: u32,
+    /// The bytes transferred so far in the phase.
+    pub bytes: u32,
+    pub path: Option<String>,
+}
+
+
+pub const IMPORTING: &str = "importing";
+pub const EXPORTING: &str = "exporting";
+pub const NEGOTIATING: &str = "negotiating";
+pub const SENDING_OBJECTS: &str = "sending-objects";
+pub const RECEIVING_OBJECTS: &str = "receiving-objects";
+pub const WRITING_FILES: &str = "writing-files";
+
+
+/// Reports the progress of a phase whose total is known before it starts.
+#[derive(Debug)]
+pub struct PhaseProgress<'a> {
+    reporter: Option<&'a ProgressReporter>,
+    phase: &'static str,
+    current: usize,
+    total: usize,
+    bytes: usize,
+}
+
+
+impl<'a> PhaseProgress<'a> {
+    /// Reports that the phase has started.
+    pub fn new(reporter: Option<&'a ProgressReporter>, phase: &'static str, total: usize) -> Self {
+        let progress = Self {
+            reporter,
+            phase,
+            current: 0,
+            total,
+            bytes: 0,
+        };
+        progress.report(None);
+        progress
+    }
+
+
+    /// Reports that `count` more items of `bytes` bytes in total have been done, the last of them `path`.
+    pub fn advance(&mut self, count: usize, bytes: usize, path: Option<&str>) {
+        self.current += count;
+        self.bytes += bytes;
+        self.report(path);
+    }
+
+
+    fn report(&self, path: Option<&str>) {
+        if let Some(reporter) = self.reporter {
+            reporter.report(Progress {
+                phase: self.phase.to_string(),
+                current: self.current as u32,
+                total: self.total as u32,
+                bytes: self.bytes as u32,
+                path: path.map(|path| path.to_string()),
+            });
+        }
+    }
+}
+
+
+#[inline]
+pub fn report(
+    reporter: Option<&ProgressReporter>,
+    phase: &str,
+    current: usize,
+    total: usize,
+    path: Option<&str>,
+) {
+    if let Some(reporter) = reporter {
+        reporter.report(Progress {
+            phase: phase.to_string(),
+            current: current as u32,
+            total: total as u32,
+            bytes: 0,
+            path: path.map(|path| path.to_string()),
+        });
+    }
+}
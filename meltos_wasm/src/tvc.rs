@@ -1,8 +1,9 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::future::Future;
 use std::panic;
 
 use meltos::user::UserId;
 use meltos_client::config::SessionConfigs;
-use meltos_client::error;
 use meltos_client::error::JsResult;
 use meltos_client::tvc::TvcClient;
 use meltos_tvc::branch::BranchName;
@@ -11,14 +12,54 @@ use meltos_tvc::object::commit::CommitHash;
 use meltos_tvc::object::ObjHash;
 use wasm_bindgen::prelude::wasm_bindgen;
 
+use crate::abort::{abort_error, AbortSignal};
+use crate::error;
+use crate::error::{classify, classify_or, IntoJsResult, PUSH_REJECTED};
+use crate::file_system::path::normalize_path;
 use crate::file_system::WasmFileSystem;
-use crate::js_vec::{JsVecBranchCommitMeta, JsVecString};
+use crate::js_vec::{JsVecBranchCommitMeta, JsVecPathCollision, JsVecString};
+use crate::progress::{
+    NEGOTIATING, PhaseProgress, ProgressReporter, RECEIVING_OBJECTS, report, SENDING_OBJECTS, WRITING_FILES,
+};
+use crate::tvc::bundle::BranchUpdate;
+use crate::tvc::collision::CollisionPolicy;
+use crate::tvc::events::{COMMITTED, FETCHED, Listeners, PUSHED, RepositoryEvent, STAGED, UNSTAGED};
+use crate::tvc::repository::{Repository, Snapshot};
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::tag::{is_tags_branch, TAGS_BRANCH};
+
+mod amend;
+mod archive;
+pub mod blame;
+mod branch;
+pub mod bundle;
+pub mod collision;
+pub mod diff;
+pub mod events;
+mod hunk;
+pub mod log;
+pub mod merge;
+pub mod merge3;
+pub mod rebase;
+mod repository;
+pub mod reset;
+mod revert;
+mod signature;
+pub mod stash;
+pub mod status;
+pub mod tag;
+mod worktree;
 
 #[wasm_bindgen(getter_with_clone)]
 #[derive(Clone)]
 pub struct WasmTvcClient {
     tvc: TvcClient<WasmFileSystem>,
     fs: WasmFileSystem,
+    collision_policy: CollisionPolicy,
+    user_id: Option<String>,
+    listeners: Listeners,
+    /// Receives the progress of the working files written by the running operation.
+    progress: Option<ProgressReporter>,
 }
 
 #[wasm_bindgen]
@@ -30,6 +71,10 @@ impl WasmTvcClient {
         Self {
             tvc: TvcClient::new(fs.clone()),
             fs: fs.clone(),
+            collision_policy: CollisionPolicy::default(),
+            user_id: None,
+            listeners: Listeners::default(),
+            progress: None,
         }
     }
 
@@ -40,130 +85,510 @@ impl WasmTvcClient {
 
     #[inline]
     pub async fn init_repository(&self, branch_name: String) -> JsResult<CommitHash> {
-        let commit_hash = self.tvc.init_repository(&BranchName(branch_name)).await?;
+        let commit_hash = self.observe_heads(None, async {
+            self.tvc.init_repository(&BranchName(branch_name)).await.map_err(classify)
+        }).await?;
         Ok(commit_hash)
     }
 
-    pub async fn branch_names(&self) -> error::Result<JsVecString> {
+    pub async fn branch_names(&self) -> JsResult<JsVecString> {
         let branch_names: Vec<String> = self
             .tvc
             .branch_names()
-            .await?
+            .await
+            .map_err(classify)?
             .into_iter()
+            .filter(|b| !is_tags_branch(b))
             .map(|b| b.to_string())
             .collect();
         Ok(JsVecString(branch_names))
     }
 
-    pub async fn unzip(&self, branch_name: String) -> JsResult {
-        self.tvc.unzip(&BranchName(branch_name)).await?;
-        Ok(())
+    /// Writes the files committed at the branch, tag or commit into the workspace.
+    ///
+    /// For a tag or a commit, the files committed at the head of the checked out branch
+    /// which it doesn't have are deleted, while untracked files are left as they are.
+    ///
+    /// A branch is unzipped by tvc in one go, so `progress` is reported when it starts and ends,
+    /// while a tag or a commit reports each file written.
+    /// Aborting `signal` restores the files written so far.
+    /// Returns the path collisions between the files under the warn collision policy.
+    pub async fn unzip(
+        &self,
+        branch_or_commit: String,
+        progress: Option<ProgressReporter>,
+        signal: Option<AbortSignal>,
+    ) -> JsResult<JsVecPathCollision> {
+        let repository = self.repository();
+        let branch_name = BranchName(branch_or_commit);
+        let commit_hash = repository.resolve(&branch_name.0).await?;
+        let collisions = self.check_unzip_collisions(&commit_hash).await?;
+        let snapshot = repository.read_snapshot(&commit_hash).await?;
+
+        self.with_progress(progress).abortable(signal, |client| async move {
+            if repository.read_head(&branch_name).await?.is_some() {
+                let mut writing = PhaseProgress::new(client.progress.as_ref(), WRITING_FILES, snapshot.len());
+                client.tvc.unzip(&branch_name).await.map_err(classify)?;
+                writing.advance(snapshot.len(), 0, None);
+                return Ok(());
+            }
+            // A tag or a commit is written without any branch being involved.
+            let mut paths: BTreeSet<String> = snapshot.keys().cloned().collect();
+            paths.extend(client.checked_out_snapshot().await?.into_keys());
+            client.restore_working_files(&snapshot, &paths).await
+        }).await?;
+        Ok(JsVecPathCollision(collisions))
     }
 
     #[inline(always)]
     pub async fn open_room(&mut self, lifetime_sec: Option<u64>, capacity: Option<u64>) -> JsResult<SessionConfigs> {
-        let session_configs = self.tvc.open_room(lifetime_sec, capacity).await?;
+        let session_configs = self.tvc.open_room(lifetime_sec, capacity).await.map_err(classify)?;
+        self.user_id = Some(session_configs.user_id.0.clone());
         Ok(session_configs)
     }
 
     #[inline(always)]
     pub async fn join_room(&mut self, room_id: String, user_id: Option<String>) -> JsResult<SessionConfigs> {
-        let session_configs = self.tvc.join_room(room_id, user_id.map(UserId)).await?;
+        let session_configs = self.tvc.join_room(room_id, user_id.map(UserId)).await.map_err(classify)?;
+        self.user_id = Some(session_configs.user_id.0.clone());
         Ok(session_configs)
     }
 
+    /// Stages the file, or the files under the directory.
+    ///
+    /// Returns the path collisions with the files staged or committed under the warn collision policy.
     #[inline(always)]
-    pub async fn stage(&self, branch_name: String, path: String) -> JsResult {
-        self.tvc.stage(&BranchName(branch_name), path).await?;
-        Ok(())
+    pub async fn stage(&self, branch_name: String, path: String) -> JsResult<JsVecPathCollision> {
+        let branch_name = BranchName(branch_name);
+        let path = normalize_path(&path);
+        let collisions = self.check_stage_collisions(&branch_name, &path).await?;
+        self.tvc.stage(&branch_name, path.clone()).await.map_err(classify)?;
+        self.emit(RepositoryEvent {
+            branch: Some(branch_name.0),
+            paths: vec![path],
+            ..RepositoryEvent::new(STAGED)
+        });
+        Ok(JsVecPathCollision(collisions))
     }
 
     #[inline(always)]
     pub async fn un_stage(&self, file_path: &str) -> JsResult {
-        self.tvc.un_stage(file_path).await?;
+        self.tvc.un_stage(file_path).await.map_err(classify)?;
+        self.emit(RepositoryEvent {
+            branch: self.unstaged_branch().await?,
+            paths: vec![file_path.to_string()],
+            ..RepositoryEvent::new(UNSTAGED)
+        });
         Ok(())
     }
 
     #[inline(always)]
     pub async fn un_stage_all(&self) -> JsResult {
-        self.tvc.un_stage_all().await?;
+        let paths = if self.is_listened(UNSTAGED) {
+            self.tvc.staging_files().await.map_err(classify)?
+        } else {
+            Vec::new()
+        };
+        self.tvc.un_stage_all().await.map_err(classify)?;
+        self.emit(RepositoryEvent {
+            branch: self.unstaged_branch().await?,
+            paths,
+            ..RepositoryEvent::new(UNSTAGED)
+        });
         Ok(())
     }
 
+    /// Commits the staged files with the user of the session as the author.
     #[inline(always)]
-    pub async fn commit(&self, branch_name: String, text: String) -> JsResult<CommitHash> {
-        Ok(self.tvc.commit(&BranchName(branch_name), text).await?)
-    }
-
-    #[inline(always)]
-    pub async fn push(&mut self, session_configs: &SessionConfigs) -> JsResult {
-        self.tvc.push(session_configs.clone()).await?;
-        Ok(())
+    pub async fn commit(&self, branch_name: String, text: String, co_authors: Option<Vec<String>>) -> JsResult<CommitHash> {
+        let branch_name = BranchName(branch_name);
+        let text = self.sign(&text, co_authors.unwrap_or_default());
+        let commit_hash = self.observe_heads(None, async {
+            self.tvc.commit(&branch_name, text).await.map_err(classify)
+        }).await?;
+        self.emit_commit(COMMITTED, &branch_name, &commit_hash);
+        Ok(commit_hash)
     }
 
-    #[inline(always)]
-    pub async fn merge(&self, branch_name: String, source_commit_hash: String) -> JsResult {
-        let _ = self.tvc.merge(BranchName(branch_name), CommitHash(ObjHash(source_commit_hash))).await?;
+    /// Pushes the local commits to the room.
+    ///
+    /// The tags are sent with the local commits.
+    /// `progress` is reported for each local commit whose objects are counted while negotiating,
+    /// and with the number of those objects when they start to be sent and when all of them have been sent,
+    /// since they are sent in a single request.
+    /// `signal` is checked only before sending, since the sent commits can't be taken back.
+    pub async fn push(
+        &mut self,
+        session_configs: &SessionConfigs,
+        progress: Option<ProgressReporter>,
+        signal: Option<AbortSignal>,
+    ) -> JsResult {
+        if signal.is_some_and(|signal| signal.aborted()) {
+            return Err(abort_error());
+        }
+        self.record_tags().await?;
+        let pushed = if self.is_listened(PUSHED) {
+            self.unpushed_branches().await?
+        } else {
+            Vec::new()
+        };
+        let (objects, bytes) = if progress.is_some() {
+            let commits = self.local_commits().await?;
+            let mut negotiating = PhaseProgress::new(progress.as_ref(), NEGOTIATING, commits.len());
+            self.count_objects(&commits, Some(&mut negotiating)).await?
+        } else {
+            report(progress.as_ref(), NEGOTIATING, 0, 0, None);
+            (0, 0)
+        };
+        let mut sending = PhaseProgress::new(progress.as_ref(), SENDING_OBJECTS, objects);
+        self.tvc.push(session_configs.clone()).await.map_err(|e| classify_or(e, PUSH_REJECTED))?;
+        sending.advance(objects, bytes, None);
+        self.emit(RepositoryEvent {
+            branches: pushed,
+            ..RepositoryEvent::new(PUSHED)
+        });
         Ok(())
     }
 
-    #[inline(always)]
-    pub async fn fetch(&self, session_configs: &SessionConfigs) -> JsResult {
-        self.tvc.fetch(session_configs.clone()).await?;
+    /// Fetches the commits of the room, and the tags which the local repository doesn't have.
+    ///
+    /// `progress` is reported when negotiating, and with the objects of the new commits once they are received,
+    /// since their number isn't known before the response.
+    /// Aborting `signal` discards the objects received so far.
+    pub async fn fetch(
+        &self,
+        session_configs: &SessionConfigs,
+        progress: Option<ProgressReporter>,
+        signal: Option<AbortSignal>,
+    ) -> JsResult {
+        report(progress.as_ref(), NEGOTIATING, 0, 0, None);
+        let old_heads = if progress.is_some() {
+            self.heads().await?.0
+        } else {
+            BTreeMap::new()
+        };
+        self.observe_heads(Some(FETCHED), self.abortable(signal, |client| async move {
+            client.tvc.fetch(session_configs.clone()).await.map_err(classify)?;
+            client.apply_received_tags().await
+        })).await?;
+        if progress.is_some() {
+            let repository = self.repository();
+            let mut received = BTreeSet::new();
+            for (branch, new_head) in self.heads().await?.0 {
+                let old_head = old_heads.get(&branch).map(|head| CommitHash(ObjHash(head.clone())));
+                let new_head = CommitHash(ObjHash(new_head));
+                received.extend(repository.commits_between(old_head.as_ref(), &new_head).await?);
+            }
+            let received: Vec<CommitHash> = received.into_iter().collect();
+            let (objects, bytes) = self.count_objects(&received, None).await?;
+            PhaseProgress::new(progress.as_ref(), RECEIVING_OBJECTS, objects).advance(objects, bytes, None);
+        }
         Ok(())
     }
 
     #[inline(always)]
     pub async fn staging_files(&self) -> JsResult<JsVecString> {
-        let files = self.tvc.staging_files().await?;
+        let files = self.tvc.staging_files().await.map_err(classify)?;
         Ok(JsVecString(files))
     }
 
     #[inline(always)]
     pub async fn read_file_from_hash(&self, obj_hash: String) -> JsResult<Option<String>> {
-        let content = self.tvc.read_file_from_hash(&ObjHash(obj_hash)).await?;
+        let content = self.tvc.read_file_from_hash(&ObjHash(obj_hash)).await.map_err(classify)?;
         Ok(content)
     }
 
     #[inline(always)]
     pub async fn all_branch_commit_metas(&self) -> JsResult<JsVecBranchCommitMeta> {
-        let branches = self.tvc.all_branch_commit_metas().await?;
+        let mut branches = self.tvc.all_branch_commit_metas().await.map_err(classify)?;
+        branches.retain(|branch| branch.name != TAGS_BRANCH);
+        // Who made the commit and when are written as trailers, which aren't a part of the message.
+        for commit in branches.iter_mut().flat_map(|branch| branch.commits.iter_mut()) {
+            commit.message = CommitInfo::parse(&commit.message).message;
+        }
         Ok(JsVecBranchCommitMeta(branches))
     }
 
     #[inline(always)]
     pub async fn can_push(&self, branch_name: String) -> JsResult<bool> {
-        Ok(self.tvc.can_push(&BranchName(branch_name)).await?)
+        Ok(self.tvc.can_push(&BranchName(branch_name)).await.map_err(classify)?)
     }
 
 
     #[inline(always)]
     pub async fn is_change(&self, branch_name: String, file_path: &str) -> JsResult<bool> {
-        Ok(self.tvc.is_change(&BranchName(branch_name), &FilePath(file_path.to_string())).await?)
+        Ok(self.tvc.is_change(&BranchName(branch_name), &FilePath(file_path.to_string())).await.map_err(classify)?)
     }
 
 
     #[inline(always)]
     pub async fn find_obj_hash_from_traces(&self, branch_name: String, file_path: &str) -> JsResult<Option<ObjHash>> {
-        let obj_hash = self.tvc.find_obj_hash_from_traces(&BranchName(branch_name), file_path).await?;
+        let obj_hash = self.tvc.find_obj_hash_from_traces(&BranchName(branch_name), file_path).await.map_err(classify)?;
         Ok(obj_hash)
     }
 
-    pub async fn sync_bundle(&self, bundle: &str) -> JsResult {
-        self.tvc.save_bundle(serde_json::from_str(bundle).unwrap()).await?;
-        Ok(())
-    }
-
     #[inline(always)]
     pub async fn leave(&self, session_configs: &SessionConfigs) -> JsResult {
-        self.tvc.leave(session_configs.clone()).await?;
+        self.tvc.leave(session_configs.clone()).await.map_err(classify)?;
         Ok(())
     }
 
     #[inline]
     pub async fn close(&self) -> JsResult {
-        self.tvc.close().await?;
+        self.tvc.close().await.map_err(classify)?;
         Ok(())
     }
 }
 
+
+impl WasmTvcClient {
+    #[inline(always)]
+    fn repository(&self) -> Repository {
+        Repository::new(self.fs.clone())
+    }
+
+
+    /// Runs `operation` with a client so that it can be aborted by `signal`.
+    ///
+    /// The client's file accesses fail once `signal` is aborted, and the files it has written or deleted
+    /// are restored before rejecting with an `AbortError`; writes made outside of it are left as they are.
+    pub(crate) async fn abortable<T, F>(
+        &self,
+        signal: Option<AbortSignal>,
+        operation: impl FnOnce(WasmTvcClient) -> F,
+    ) -> error::Result<T>
+    where
+        F: Future<Output = error::Result<T>>,
+    {
+        let Some(signal) = signal else {
+            return operation(self.clone()).await;
+        };
+        if signal.aborted() {
+            return Err(abort_error());
+        }
+
+        let fs = self.fs.transactional(signal.clone());
+        let result = operation(self.with_fs(fs.clone())).await;
+        if result.is_err() && signal.aborted() {
+            fs.rollback().await.into_js_result()?;
+            return Err(abort_error());
+        }
+        result
+    }
+
+
+    /// Clones the client so that it reads and writes through `fs`.
+    #[inline]
+    fn with_fs(&self, fs: WasmFileSystem) -> Self {
+        Self {
+            tvc: TvcClient::new(fs.clone()),
+            fs,
+            ..self.clone()
+        }
+    }
+
+
+    /// Clones the client so that the working files it writes are reported to `progress`.
+    #[inline]
+    fn with_progress(&self, progress: Option<ProgressReporter>) -> Self {
+        Self {
+            progress,
+            ..self.clone()
+        }
+    }
+
+
+    /// Reads the files committed at the head of the checked out branch, empty if there is none.
+    async fn checked_out_snapshot(&self) -> error::Result<Snapshot> {
+        let repository = self.repository();
+        let Some(branch_name) = repository.read_working_branch().await? else {
+            return Ok(Snapshot::new());
+        };
+        match repository.read_head(&branch_name).await? {
+            Some(head) => repository.read_snapshot(&head).await,
+            None => Ok(Snapshot::new()),
+        }
+    }
+
+
+    /// Reads the checked out branch, whose staged files are unstaged, if `unstaged` is listened.
+    async fn unstaged_branch(&self) -> error::Result<Option<String>> {
+        if !self.is_listened(UNSTAGED) {
+            return Ok(None);
+        }
+        Ok(self.repository().read_working_branch().await?.map(|branch_name| branch_name.0))
+    }
+
+
+    /// Lists the branches which have commits to push, with their heads.
+    async fn unpushed_branches(&self) -> error::Result<Vec<BranchUpdate>> {
+        let repository = self.repository();
+        let mut branches = Vec::new();
+        for branch_name in self.tvc.branch_names().await.map_err(classify)? {
+            if is_tags_branch(&branch_name) || repository.read_local_commits(&branch_name).await?.is_empty() {
+                continue;
+            }
+            if let Some(head) = repository.read_head(&branch_name).await? {
+                branches.push(BranchUpdate {
+                    branch: branch_name.0,
+                    old_head: None,
+                    new_head: head.0.0,
+                });
+            }
+        }
+        Ok(branches)
+    }
+
+
+    /// Reads the commits which the remote already has:
+    /// those reachable from the head of a branch except the local commits of that branch.
+    async fn remote_commits(&self) -> error::Result<HashSet<CommitHash>> {
+        let repository = self.repository();
+        let mut commits = HashSet::new();
+        for branch_name in self.tvc.branch_names().await.map_err(classify)? {
+            let Some(head) = repository.read_head(&branch_name).await? else {
+                continue;
+            };
+            let local_commits: HashSet<CommitHash> = repository
+                .read_local_commits(&branch_name)
+                .await?
+                .into_iter()
+                .collect();
+            commits.extend(
+                repository
+                    .ancestors(&head)
+                    .await?
+                    .into_iter()
+                    .filter(|commit_hash| !local_commits.contains(commit_hash)),
+            );
+        }
+        Ok(commits)
+    }
+
+
+    /// Reads the local commits of every branch.
+    async fn local_commits(&self) -> error::Result<Vec<CommitHash>> {
+        let repository = self.repository();
+        let mut commits = BTreeSet::new();
+        for branch_name in self.tvc.branch_names().await.map_err(classify)? {
+            commits.extend(repository.read_local_commits(&branch_name).await?);
+        }
+        Ok(commits.into_iter().collect())
+    }
+
+
+    /// Counts the objects of the commits and their compressed bytes:
+    /// each commit, its tree of the committed files and the committed files.
+    ///
+    /// `progress` advances by each commit counted.
+    async fn count_objects(
+        &self,
+        commits: &[CommitHash],
+        mut progress: Option<&mut PhaseProgress<'_>>,
+    ) -> error::Result<(usize, usize)> {
+        let repository = self.repository();
+        let mut obj_hashes = HashSet::new();
+        for commit_hash in commits {
+            let commit = repository.read_commit(commit_hash).await?;
+            obj_hashes.insert(commit_hash.0.clone());
+            obj_hashes.insert(commit.committed_objs_tree.0.clone());
+            obj_hashes.extend(repository.read_tree(&commit.committed_objs_tree).await?.into_values());
+            if let Some(progress) = progress.as_deref_mut() {
+                progress.advance(1, 0, None);
+            }
+        }
+        let mut bytes = 0;
+        for obj_hash in &obj_hashes {
+            if let Some(compressed_buf) = repository.read_compressed_obj(obj_hash).await? {
+                bytes += compressed_buf.len();
+            }
+        }
+        Ok((obj_hashes.len(), bytes))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::js_sys::{Object, Reflect};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::abort::AbortSignal;
+    use crate::error::{IO, TvcError};
+    use crate::progress::{Progress, ProgressReporter, WRITING_FILES};
+    use crate::tests::{commit_files, tvc_client, write_files};
+
+    fn signal() -> Object {
+        let signal = Object::new();
+        Reflect::set(&signal, &"aborted".into(), &false.into()).unwrap();
+        signal
+    }
+
+    fn abort(signal: &Object) {
+        Reflect::set(signal, &"aborted".into(), &true.into()).unwrap();
+    }
+
+    /// Creates a reporter which records the phase, current and total of each progress reported.
+    fn reporter() -> (ProgressReporter, Rc<RefCell<Vec<(String, u32, u32)>>>) {
+        let reported = Rc::new(RefCell::new(Vec::new()));
+        let recorded = reported.clone();
+        let callback = Closure::<dyn FnMut(Progress)>::new(move |progress: Progress| {
+            recorded.borrow_mut().push((progress.phase, progress.current, progress.total));
+        });
+        let reporter = Object::new();
+        Reflect::set(&reporter, &"report".into(), callback.as_ref()).unwrap();
+        callback.forget();
+        (reporter.unchecked_into(), reported)
+    }
+
+    #[wasm_bindgen_test]
+    async fn unzip_branch_reports_writing_files() {
+        let client = tvc_client("unzip_progress").await;
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "a"), ("workspace/b.txt", "b")]).await;
+        let files = client.repository().read_snapshot(&head).await.unwrap().len() as u32;
+        client.fs.delete("workspace/a.txt").await.unwrap();
+
+        let (reporter, reported) = reporter();
+        client.unzip("main".to_string(), Some(reporter), None).await.unwrap();
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(*reported.borrow(), vec![
+            (WRITING_FILES.to_string(), 0, files),
+            (WRITING_FILES.to_string(), files, files),
+        ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn abort_restores_only_the_writes_of_the_operation() {
+        let client = tvc_client("abortable_rollback").await;
+        write_files(&client, &[("workspace/a.txt", "a")]).await;
+        let user_fs = client.fs();
+        let signal = signal();
+
+        let result = client.abortable(Some(signal.clone().unchecked_into::<AbortSignal>()), |operation| async move {
+            operation.fs.write_file("workspace/a.txt", b"operation").await.unwrap();
+            operation.fs.write_file("workspace/dir/b.txt", b"operation").await.unwrap();
+            operation.fs.create_dir("workspace/empty").await.unwrap();
+            // The user edits a file while the operation is running.
+            user_fs.write_file("workspace/user.txt", b"user").await.unwrap();
+            abort(&signal);
+            assert!(operation.fs.write_file("workspace/c.txt", b"operation").await.is_err());
+            Err::<(), _>(TvcError::new(IO, "interrupted").into())
+        }).await;
+
+        let error = result.unwrap_err();
+        assert_eq!(Reflect::get(&error, &"name".into()).unwrap(), "AbortError");
+        let fs = client.fs();
+        assert_eq!(fs.read_file("workspace/a.txt").await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(fs.read_file("workspace/dir/b.txt").await.unwrap(), None);
+        assert_eq!(fs.read_dir("workspace/dir").await.unwrap(), None);
+        assert_eq!(fs.read_dir("workspace/empty").await.unwrap(), None);
+        assert_eq!(fs.read_file("workspace/c.txt").await.unwrap(), None);
+        assert_eq!(fs.read_file("workspace/user.txt").await.unwrap(), Some(b"user".to_vec()));
+    }
+}
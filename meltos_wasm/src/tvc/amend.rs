@@ -0,0 +1,56 @@
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::object::commit::CommitHash;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{classify, INVALID_OPERATION, TvcError};
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::WasmTvcClient;
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Replaces the last commit of the branch with one which also contains the staged files.
+    ///
+    /// The message is replaced with `text` if given, and the author is kept.
+    /// Only a commit which hasn't been pushed yet can be amended.
+    pub async fn amend(&self, branch_name: String, text: Option<String>) -> error::Result<CommitHash> {
+        self.observe_heads(None, async {
+            let branch_name = BranchName(branch_name);
+            let repository = self.repository();
+            self.ensure_not_merging().await?;
+            let Some(head) = repository.read_head(&branch_name).await? else {
+                return Err(TvcError::not_found("branch", &branch_name).into());
+            };
+            let mut local_commits = repository.read_local_commits(&branch_name).await?;
+            if local_commits.last() != Some(&head) {
+                return Err(TvcError::new(
+                    INVALID_OPERATION,
+                    format!("the last commit has already been pushed: {branch_name}"),
+                ).into());
+            }
+
+            let commit = repository.read_commit(&head).await?;
+            let mut snapshot = repository.read_snapshot(&head).await?;
+            for (path, obj_hash) in repository.read_staged_changes().await? {
+                match obj_hash {
+                    Some(obj_hash) => snapshot.insert(path, obj_hash),
+                    None => snapshot.remove(&path),
+                };
+            }
+            let mut info = CommitInfo::parse(&commit.text.0);
+            if let Some(text) = text {
+                info = info.with_message(CommitInfo::parse(&text).message);
+            }
+
+            let author = info.author.take();
+            let text = self.sign_as(&info.to_text(), author, Vec::new());
+            let commit_hash = repository.write_detached_commit(commit.parents, text, &snapshot).await?;
+            repository.write_head(&branch_name, &commit_hash).await?;
+            local_commits.pop();
+            local_commits.push(commit_hash.clone());
+            repository.write_local_commits(&branch_name, local_commits).await?;
+            self.tvc.un_stage_all().await.map_err(classify)?;
+            Ok(commit_hash)
+        }).await
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{Cursor, Write};
+use std::str::FromStr;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_futures::js_sys::Date;
+
+use crate::error;
+use crate::error::{IntoJsResult, INVALID_OPERATION, TvcError};
+use crate::js_vec::JsVecU8;
+use crate::tvc::repository::Repository;
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::WasmTvcClient;
+
+const FILE_MODE: u32 = 0o644;
+
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+
+impl FromStr for ArchiveFormat {
+    type Err = JsValue;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "zip" => Ok(Self::Zip),
+            "tar" => Ok(Self::Tar),
+            "tar.gz" | "tgz" => Ok(Self::TarGz),
+            _ => Err(TvcError::new(INVALID_OPERATION, format!("unsupported archive format: {format}")).into()),
+        }
+    }
+}
+
+
+/// A file committed in a snapshot, with its path relative to the workspace.
+struct ArchiveEntry {
+    path: String,
+    buf: Vec<u8>,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Builds an archive of the files committed at `branch_or_commit`
+    /// without touching the working files.
+    ///
+    /// `format` is one of `zip`, `tar` or `tar.gz`.
+    /// Every entry is stamped with the time of the commit, so the same commit always gives the same archive.
+    pub async fn export_archive(&self, branch_or_commit: String, format: String) -> error::Result<JsVecU8> {
+        let format = ArchiveFormat::from_str(&format)?;
+        let repository = self.repository();
+        let commit_hash = repository.resolve(&branch_or_commit).await?;
+        let entries = read_entries(&repository, &commit_hash).await?;
+        let mtime_sec = mtime_sec(&repository.read_commit(&commit_hash).await?.text.0);
+
+        let buf = match format {
+            ArchiveFormat::Zip => write_zip(&entries, mtime_sec),
+            ArchiveFormat::Tar => write_tar(Vec::new(), &entries, mtime_sec),
+            ArchiveFormat::TarGz => {
+                let encoder = GzEncoder::new(Vec::new(), Compression::default());
+                write_tar(encoder, &entries, mtime_sec).and_then(|encoder| encoder.finish())
+            }
+        };
+        Ok(JsVecU8(buf.into_js_result()?))
+    }
+}
+
+
+async fn read_entries(
+    repository: &Repository,
+    commit_hash: &meltos_tvc::object::commit::CommitHash,
+) -> error::Result<Vec<ArchiveEntry>> {
+    let snapshot = repository.read_snapshot(commit_hash).await?;
+    let mut entries = Vec::with_capacity(snapshot.len());
+    for (path, obj_hash) in snapshot {
+        entries.push(ArchiveEntry {
+            path: archive_path(&path),
+            buf: repository.read_file_obj(&obj_hash).await?,
+        });
+    }
+    Ok(entries)
+}
+
+
+fn write_tar<W: Write>(writer: W, entries: &[ArchiveEntry], mtime_sec: u64) -> std::io::Result<W> {
+    let mut builder = tar::Builder::new(writer);
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(entry.buf.len() as u64);
+        header.set_mode(FILE_MODE);
+        header.set_mtime(mtime_sec);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder.append_data(&mut header, &entry.path, entry.buf.as_slice())?;
+    }
+    builder.into_inner()
+}
+
+
+fn write_zip(entries: &[ArchiveEntry], mtime_sec: u64) -> std::io::Result<Vec<u8>> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let mut options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(FILE_MODE);
+    if let Some(last_modified) = zip_date_time(mtime_sec) {
+        options = options.last_modified_time(last_modified);
+    }
+
+    for entry in entries {
+        zip.start_file(entry.path.as_str(), options).map_err(std::io::Error::other)?;
+        zip.write_all(&entry.buf)?;
+    }
+    Ok(zip.finish().map_err(std::io::Error::other)?.into_inner())
+}
+
+
+fn zip_date_time(mtime_sec: u64) -> Option<zip::DateTime> {
+    let date = Date::new(&JsValue::from_f64(mtime_sec as f64 * 1000.));
+    zip::DateTime::from_date_and_time(
+        date.get_utc_full_year() as u16,
+        date.get_utc_month() as u8 + 1,
+        date.get_utc_date() as u8,
+        date.get_utc_hours() as u8,
+        date.get_utc_minutes() as u8,
+        date.get_utc_seconds() as u8,
+    ).ok()
+}
+
+
+/// The time of the commit in seconds, or the unix epoch if the commit doesn't have it.
+#[inline]
+fn mtime_sec(commit_text: &str) -> u64 {
+    CommitInfo::parse(commit_text).timestamp.map_or(0, |timestamp| timestamp / 1000)
+}
+
+
+#[inline]
+fn archive_path(path: &str) -> String {
+    path
+        .trim_start_matches('/')
+        .trim_start_matches("workspace/")
+        .to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tvc::archive::{archive_path, ArchiveEntry, mtime_sec, write_tar, write_zip};
+
+    const MTIME_SEC: u64 = 1_700_000_000;
+
+    fn entries() -> Vec<ArchiveEntry> {
+        vec![ArchiveEntry {
+            path: archive_path("workspace/src/hello.txt"),
+            buf: b"hello".to_vec(),
+        }]
+    }
+
+    #[wasm_bindgen_test]
+    fn mtime_from_commit_timestamp() {
+        assert_eq!(mtime_sec("commit\n\nAuthor: alice\nTimestamp: 1700000000123"), MTIME_SEC);
+        assert_eq!(mtime_sec("commit"), 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn tar_keeps_mtime() {
+        let tar = write_tar(Vec::new(), &entries(), MTIME_SEC).unwrap();
+        assert_eq!(tar, write_tar(Vec::new(), &entries(), MTIME_SEC).unwrap());
+
+        let mut archive = tar::Archive::new(tar.as_slice());
+        let entry = archive.entries().unwrap().next().unwrap().unwrap();
+        assert_eq!(entry.path().unwrap().to_str(), Some("src/hello.txt"));
+        assert_eq!(entry.header().mtime().unwrap(), MTIME_SEC);
+    }
+
+    #[wasm_bindgen_test]
+    fn zip_keeps_mtime() {
+        let zip = write_zip(&entries(), MTIME_SEC).unwrap();
+        assert_eq!(zip, write_zip(&entries(), MTIME_SEC).unwrap());
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(zip)).unwrap();
+        let file = archive.by_index(0).unwrap();
+        assert_eq!(file.name(), "src/hello.txt");
+        let modified = file.last_modified();
+        assert_eq!((modified.year(), modified.month(), modified.day()), (2023, 11, 14));
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use meltos_tvc::object::commit::CommitHash;
+use meltos_tvc::object::ObjHash;
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffTag};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{INVALID_OPERATION, TvcError};
+use crate::js_vec::JsVecBlameLine;
+use crate::tvc::repository::Repository;
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::WasmTvcClient;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BlameOptions {
+    /// The first line to blame, 1-based.
+    pub start: Option<u32>,
+    /// The last line to blame, inclusive.
+    pub end: Option<u32>,
+}
+
+
+/// A line of a file with the commit which last changed it.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlameLine {
+    /// The line number, 1-based.
+    pub line: u32,
+    pub content: String,
+    pub commit: String,
+    pub author: Option<String>,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: Option<u64>,
+}
+
+
+/// The objects and texts of the blamed file read so far,
+/// since a commit is reached through every path of merges to it.
+struct BlameCache {
+    repository: Repository,
+    path: String,
+    /// The object of the file at each commit, `None` if the file doesn't exist there.
+    objs: HashMap<CommitHash, Option<ObjHash>>,
+    texts: HashMap<ObjHash, String>,
+}
+
+
+/// Lines of a file at a commit which are not blamed yet.
+struct PendingLines {
+    commit_hash: CommitHash,
+    obj_hash: ObjHash,
+    /// Pairs of the index in the blamed file and the index in the file at the commit.
+    lines: Vec<(usize, usize)>,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Finds the commit which last changed each line of the file at `branch_or_commit`.
+    ///
+    /// `options` is an object of `{ start, end }` to blame only these lines; both are optional.
+    /// The history is walked until every line is blamed, so a narrower range stops earlier.
+    pub async fn blame(&self, branch_or_commit: String, path: String, options: JsValue) -> error::Result<JsVecBlameLine> {
+        let options: BlameOptions = if options.is_undefined() || options.is_null() {
+            BlameOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(|e| TvcError::new(INVALID_OPERATION, e.to_string()))?
+        };
+        let repository = self.repository();
+        let head = repository.resolve(&branch_or_commit).await?;
+        let mut cache = BlameCache {
+            repository: repository.clone(),
+            path,
+            objs: HashMap::new(),
+            texts: HashMap::new(),
+        };
+        let Some(obj_hash) = cache.obj_at(&head).await? else {
+            return Err(TvcError::not_found("file", &cache.path).into());
+        };
+        let text = cache.text(&obj_hash).await?.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+
+        let start = options.start.map_or(0, |start| start.saturating_sub(1) as usize).min(lines.len());
+        let end = options.end.map_or(lines.len(), |end| end as usize).clamp(start, lines.len());
+        let mut blamed: Vec<Option<CommitHash>> = vec![None; end - start];
+        let mut pending = vec![PendingLines {
+            commit_hash: head,
+            obj_hash,
+            lines: (start..end).map(|i| (i, i)).collect(),
+        }];
+        while let Some(PendingLines { commit_hash, obj_hash, lines: mut remaining }) = pending.pop() {
+            let commit = repository.read_commit(&commit_hash).await?;
+            for parent in &commit.parents {
+                if remaining.is_empty() {
+                    break;
+                }
+                let Some(parent_obj_hash) = cache.obj_at(parent).await? else {
+                    continue;
+                };
+                let passed = if parent_obj_hash == obj_hash {
+                    std::mem::take(&mut remaining)
+                } else {
+                    let mapping = cache.map_lines(&parent_obj_hash, &obj_hash).await?;
+                    let mut passed = Vec::new();
+                    remaining.retain(|&(i, line)| match mapping.get(line).copied().flatten() {
+                        Some(parent_line) => {
+                            passed.push((i, parent_line));
+                            false
+                        }
+                        None => true,
+                    });
+                    passed
+                };
+                if !passed.is_empty() {
+                    pending.push(PendingLines {
+                        commit_hash: parent.clone(),
+                        obj_hash: parent_obj_hash,
+                        lines: passed,
+                    });
+                }
+            }
+            for (i, _) in remaining {
+                blamed[i - start] = Some(commit_hash.clone());
+            }
+        }
+
+        let mut infos = HashMap::new();
+        let mut blame_lines = Vec::with_capacity(blamed.len());
+        for (i, commit_hash) in blamed.into_iter().enumerate() {
+            let Some(commit_hash) = commit_hash else {
+                continue;
+            };
+            if !infos.contains_key(&commit_hash) {
+                let commit = repository.read_commit(&commit_hash).await?;
+                infos.insert(commit_hash.clone(), CommitInfo::parse(&commit.text.0));
+            }
+            let info = &infos[&commit_hash];
+            blame_lines.push(BlameLine {
+                line: (start + i) as u32 + 1,
+                content: lines[start + i].to_string(),
+                commit: commit_hash.0.0,
+                author: info.author.clone(),
+                timestamp: info.timestamp,
+            });
+        }
+        Ok(JsVecBlameLine(blame_lines))
+    }
+}
+
+
+impl BlameCache {
+    async fn obj_at(&mut self, commit_hash: &CommitHash) -> error::Result<Option<ObjHash>> {
+        if let Some(obj_hash) = self.objs.get(commit_hash) {
+            return Ok(obj_hash.clone());
+        }
+        let obj_hash = self.repository.read_snapshot(commit_hash).await?.remove(&self.path);
+        self.objs.insert(commit_hash.clone(), obj_hash.clone());
+        Ok(obj_hash)
+    }
+
+
+    async fn text(&mut self, obj_hash: &ObjHash) -> error::Result<&str> {
+        if !self.texts.contains_key(obj_hash) {
+            let buf = self.repository.read_file_obj(obj_hash).await?;
+            let text = String::from_utf8(buf).map_err(|_| TvcError::new(
+                INVALID_OPERATION,
+                format!("can't blame a binary file: {}", self.path),
+            ))?;
+            self.texts.insert(obj_hash.clone(), text);
+        }
+        Ok(&self.texts[obj_hash])
+    }
+
+
+    async fn map_lines(&mut self, parent: &ObjHash, child: &ObjHash) -> error::Result<Vec<Option<usize>>> {
+        self.text(parent).await?;
+        self.text(child).await?;
+        Ok(map_lines(&self.texts[parent], &self.texts[child]))
+    }
+}
+
+
+/// Maps each line of `child` to the same line in `parent`, or `None` if it was changed.
+fn map_lines(parent: &str, child: &str) -> Vec<Option<usize>> {
+    let parent_lines: Vec<&str> = parent.lines().collect();
+    let child_lines: Vec<&str> = child.lines().collect();
+    let mut mapping = vec![None; child_lines.len()];
+    for op in similar::capture_diff_slices(Algorithm::Myers, &parent_lines, &child_lines) {
+        if op.tag() == DiffTag::Equal {
+            for (parent_line, child_line) in op.old_range().zip(op.new_range()) {
+                mapping[child_line] = Some(parent_line);
+            }
+        }
+    }
+    mapping
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use meltos_tvc::object::commit::CommitHash;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, diverged_client, tvc_client};
+    use crate::tvc::blame::{BlameOptions, map_lines};
+    use crate::tvc::WasmTvcClient;
+
+    async fn blamed_commits(client: &WasmTvcClient, options: JsValue) -> Vec<(u32, String, String)> {
+        client
+            .blame("main".to_string(), "workspace/a.txt".to_string(), options)
+            .await
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|line| (line.line, line.content, line.commit))
+            .collect()
+    }
+
+    fn line(line: u32, content: &str, commit: &CommitHash) -> (u32, String, String) {
+        (line, content.to_string(), commit.0.0.clone())
+    }
+
+    #[wasm_bindgen_test]
+    fn map_unchanged_lines() {
+        assert_eq!(map_lines("a\nb\nc\n", "a\nb\nc\n"), vec![Some(0), Some(1), Some(2)]);
+    }
+
+    #[wasm_bindgen_test]
+    fn changed_lines_are_not_mapped() {
+        assert_eq!(map_lines("a\nb\nc\n", "x\na\nc\nd\n"), vec![None, Some(0), Some(2), None]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn blame_last_change_of_each_line() {
+        let client = tvc_client("blame_history").await;
+        let first = commit_files(&client, "main", &[("workspace/a.txt", "a\nb\nc\n")]).await;
+        let second = commit_files(&client, "main", &[("workspace/a.txt", "a\nB\nc\n")]).await;
+        let third = commit_files(&client, "main", &[("workspace/a.txt", "a\nB\nc\nd\n")]).await;
+
+        assert_eq!(blamed_commits(&client, JsValue::UNDEFINED).await, vec![
+            line(1, "a", &first),
+            line(2, "B", &second),
+            line(3, "c", &first),
+            line(4, "d", &third),
+        ]);
+
+        let options = serde_wasm_bindgen::to_value(&BlameOptions {
+            start: Some(2),
+            end: Some(3),
+        }).unwrap();
+        assert_eq!(blamed_commits(&client, options).await, vec![line(2, "B", &second), line(3, "c", &first)]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn blame_through_merge() {
+        let client = diverged_client(
+            "blame_merge",
+            &[("workspace/a.txt", "1\n2\n3\n4\n5\n")],
+            &[("workspace/a.txt", "one\n2\n3\n4\n5\n")],
+            &[("workspace/a.txt", "1\n2\n3\n4\nfive\n")],
+        ).await;
+        let repository = client.repository();
+        let ours = repository.read_head(&BranchName("main".to_string())).await.unwrap().unwrap();
+        let theirs = repository.read_head(&BranchName("feature".to_string())).await.unwrap().unwrap();
+        let base = repository.read_commit(&ours).await.unwrap().parents[0].clone();
+        client.merge("main".to_string(), "feature".to_string(), None, None, None).await.unwrap();
+
+        assert_eq!(blamed_commits(&client, JsValue::UNDEFINED).await, vec![
+            line(1, "one", &ours),
+            line(2, "2", &base),
+            line(3, "3", &base),
+            line(4, "4", &base),
+            line(5, "five", &theirs),
+        ]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn refuse_binary_and_missing_files() {
+        let client = tvc_client("blame_refuse").await;
+        client.fs.write_file("workspace/a.bin", &[0xff, 0x00]).await.unwrap();
+        commit_files(&client, "main", &[]).await;
+
+        assert!(client.blame("main".to_string(), "workspace/a.bin".to_string(), JsValue::UNDEFINED).await.is_err());
+        assert!(client.blame("main".to_string(), "workspace/b.txt".to_string(), JsValue::UNDEFINED).await.is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use meltos_tvc::object::commit::CommitHash;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::abort::AbortSignal;
+use crate::error;
+use crate::error::{classify, IntoJsResult, INVALID_OPERATION, TvcError};
+use crate::tvc::merge::MERGE_STATE;
+use crate::tvc::rebase::REBASE_STATE;
+use crate::tvc::status::UNTRACKED;
+use crate::tvc::WasmTvcClient;
+use crate::tvc::worktree::Tree;
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Creates a new branch whose head is `from`, a branch name or a commit hash.
+    ///
+    /// The new branch starts with no local commits.
+    pub async fn create_branch(&self, branch_name: String, from: String) -> error::Result {
+        self.observe_heads(None, async {
+            validate_ref_name("branch", &branch_name)?;
+            let branch_name = BranchName(branch_name);
+            let repository = self.repository();
+            self.ensure_not_exists_branch(&branch_name).await?;
+
+            let head = repository.resolve(&from).await?;
+            repository.write_head(&branch_name, &head).await?;
+            repository.write_local_commits(&branch_name, Vec::new()).await
+        }).await
+    }
+
+    /// Deletes the branch.
+    ///
+    /// The branch currently checked out can't be deleted,
+    /// and a branch with unpushed commits is deleted only if `force` is true.
+    pub async fn delete_branch(&self, branch_name: String, force: Option<bool>) -> error::Result {
+        let branch_name = BranchName(branch_name);
+        let repository = self.repository();
+        self.ensure_exists_branch(&branch_name).await?;
+        if repository.read_working_branch().await?.as_ref() == Some(&branch_name) {
+            return Err(TvcError::new(
+                INVALID_OPERATION,
+                format!("can't delete the checked out branch: {branch_name}"),
+            ).into());
+        }
+        if !force.unwrap_or(false) && !repository.read_local_commits(&branch_name).await?.is_empty() {
+            return Err(TvcError::new(INVALID_OPERATION, format!("branch has unpushed commits: {branch_name}")).into());
+        }
+
+        repository.delete_head(&branch_name).await?;
+        repository.delete_local_commits(&branch_name).await
+    }
+
+    pub async fn rename_branch(&self, old_name: String, new_name: String) -> error::Result {
+        self.observe_heads(None, async {
+            validate_ref_name("branch", &new_name)?;
+            let old_name = BranchName(old_name);
+            let new_name = BranchName(new_name);
+            let repository = self.repository();
+            let head = self.ensure_exists_branch(&old_name).await?;
+            self.ensure_not_exists_branch(&new_name).await?;
+
+            let local_commits = repository.read_local_commits(&old_name).await?;
+            repository.write_head(&new_name, &head).await?;
+            repository.write_local_commits(&new_name, local_commits).await?;
+            repository.delete_head(&old_name).await?;
+            repository.delete_local_commits(&old_name).await?;
+            if repository.read_working_branch().await?.as_ref() == Some(&old_name) {
+                repository.write_working_branch(&new_name).await?;
+            }
+            Ok(())
+        }).await
+    }
+
+    /// Switches the working branch and rewrites the working files with its head.
+    ///
+    /// Refuses if a merge or a rebase is in progress, or if there are uncommitted changes
+    /// or untracked files which would be overwritten, unless `force` is true;
+    /// in that case the merge or the rebase is dropped and the changes are discarded.
+    pub async fn checkout(&self, branch_name: String, force: Option<bool>, signal: Option<AbortSignal>) -> error::Result {
+        self.observe_heads(None, self.abortable(signal, |client| async move {
+            let branch_name = BranchName(branch_name);
+            let repository = client.repository();
+            client.ensure_exists_branch(&branch_name).await?;
+            let target = client.head_tree(&branch_name).await?;
+            let current_branch = repository.read_working_branch().await?;
+            let current = match current_branch.as_ref() {
+                Some(current_branch) => client.head_tree(current_branch).await?,
+                None => Tree::new(),
+            };
+
+            if force.unwrap_or(false) {
+                if client.is_merging().await? {
+                    repository.delete_state(MERGE_STATE).await?;
+                }
+                if client.is_rebasing().await? {
+                    repository.delete_state(REBASE_STATE).await?;
+                }
+                client.tvc.un_stage_all().await.map_err(classify)?;
+            } else {
+                client.ensure_not_merging().await?;
+                client.ensure_not_rebasing().await?;
+                if let Some(current_branch) = current_branch.as_ref() {
+                    client.ensure_clean(current_branch, &target).await?;
+                }
+            }
+
+            for path in current.keys().filter(|path| !target.contains_key(*path)) {
+                client.fs.delete(path).await.into_js_result()?;
+            }
+            repository.write_working_branch(&branch_name).await?;
+            client.unzip(branch_name.0, None, None).await?;
+            Ok(())
+        })).await
+    }
+}
+
+
+impl WasmTvcClient {
+    async fn ensure_exists_branch(&self, branch_name: &BranchName) -> error::Result<CommitHash> {
+        self
+            .repository()
+            .read_head(branch_name)
+            .await?
+            .ok_or_else(|| TvcError::not_found("branch", &branch_name).into())
+    }
+
+
+    async fn ensure_not_exists_branch(&self, branch_name: &BranchName) -> error::Result {
+        if self.repository().read_head(branch_name).await?.is_some() {
+            Err(TvcError::new(INVALID_OPERATION, format!("branch already exists: {branch_name}")).into())
+        } else {
+            Ok(())
+        }
+    }
+
+
+    /// Fails if there are uncommitted changes on the branch,
+    /// or untracked files which differ from the files in `target`.
+    async fn ensure_clean(&self, branch_name: &BranchName, target: &Tree) -> error::Result {
+        let working = self.working_tree().await?;
+        for entry in self.status_entries(branch_name).await? {
+            let overwritten = if entry.status == UNTRACKED {
+                match (target.get(&entry.path), working.get(&entry.path)) {
+                    (Some(target_blob), Some(working_blob)) => !self.is_same_blob(target_blob, working_blob).await?,
+                    _ => false,
+                }
+            } else {
+                true
+            };
+            if overwritten {
+                return Err(TvcError::conflict(
+                    format!("uncommitted changes would be overwritten by checkout: {}", entry.path),
+                    [entry.path],
+                ).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Validates the name of a branch or a tag, labelled `kind` in the error.
+pub(crate) fn validate_ref_name(kind: &str, name: &str) -> error::Result {
+    let invalid = name.is_empty()
+        || name.starts_with('.')
+        || name.starts_with('/')
+        || name.ends_with('/')
+        || name.contains("..")
+        || name.contains("//")
+        || name.chars().any(|c| c.is_whitespace() || c.is_control());
+    if invalid {
+        Err(TvcError::new(INVALID_OPERATION, format!("invalid {kind} name: {name}")).into())
+    } else {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, diverged_client, tvc_client, write_files};
+
+    fn branch(name: &str) -> BranchName {
+        BranchName(name.to_string())
+    }
+
+    #[wasm_bindgen_test]
+    async fn create_branch_without_local_commits() {
+        let client = tvc_client("branch_create").await;
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        let repository = client.repository();
+        assert!(!repository.read_local_commits(&branch("main")).await.unwrap().is_empty());
+
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        assert_eq!(repository.read_head(&branch("feature")).await.unwrap(), Some(head));
+        assert_eq!(repository.read_local_commits(&branch("feature")).await.unwrap(), Vec::new());
+
+        assert!(client.create_branch("feature".to_string(), "main".to_string()).await.is_err());
+        assert!(client.create_branch("a..b".to_string(), "main".to_string()).await.is_err());
+        assert!(client.create_branch(".tags".to_string(), "main".to_string()).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn delete_branch() {
+        let client = tvc_client("branch_delete").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        commit_files(&client, "feature", &[("workspace/a.txt", "b")]).await;
+
+        assert!(client.delete_branch("feature".to_string(), None).await.is_err());
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        assert!(client.delete_branch("feature".to_string(), None).await.is_err());
+        client.delete_branch("feature".to_string(), Some(true)).await.unwrap();
+
+        let repository = client.repository();
+        assert_eq!(repository.read_head(&branch("feature")).await.unwrap(), None);
+        assert_eq!(repository.read_local_commits(&branch("feature")).await.unwrap(), Vec::new());
+    }
+
+    #[wasm_bindgen_test]
+    async fn rename_checked_out_branch() {
+        let client = tvc_client("branch_rename").await;
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        let repository = client.repository();
+        let local_commits = repository.read_local_commits(&branch("main")).await.unwrap();
+
+        client.rename_branch("main".to_string(), "trunk".to_string()).await.unwrap();
+        assert_eq!(repository.read_head(&branch("trunk")).await.unwrap(), Some(head));
+        assert_eq!(repository.read_local_commits(&branch("trunk")).await.unwrap(), local_commits);
+        assert_eq!(repository.read_head(&branch("main")).await.unwrap(), None);
+        assert_eq!(repository.read_local_commits(&branch("main")).await.unwrap(), Vec::new());
+        assert_eq!(repository.read_working_branch().await.unwrap(), Some(branch("trunk")));
+    }
+
+    #[wasm_bindgen_test]
+    async fn checkout_rewrites_working_files() {
+        let client = tvc_client("branch_checkout").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        client.fs.delete("workspace/a.txt").await.unwrap();
+        commit_files(&client, "feature", &[("workspace/b.txt", "b")]).await;
+
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), None);
+        assert_eq!(client.repository().read_working_branch().await.unwrap(), Some(branch("main")));
+    }
+
+    #[wasm_bindgen_test]
+    async fn checkout_refuses_uncommitted_changes_unless_forced() {
+        let client = tvc_client("branch_checkout_changes").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        write_files(&client, &[("workspace/a.txt", "changed")]).await;
+
+        assert!(client.checkout("feature".to_string(), None, None).await.is_err());
+        assert_eq!(client.repository().read_working_branch().await.unwrap(), Some(branch("main")));
+
+        client.checkout("feature".to_string(), Some(true), None).await.unwrap();
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"a".to_vec()));
+        assert_eq!(client.repository().read_working_branch().await.unwrap(), Some(branch("feature")));
+    }
+
+    #[wasm_bindgen_test]
+    async fn checkout_refuses_merge_in_progress_unless_forced() {
+        let client = diverged_client(
+            "branch_checkout_merging",
+            &[("workspace/a.txt", "base\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n")],
+        ).await;
+        client.merge("main".to_string(), "feature".to_string(), None, None, None).await.unwrap();
+        assert!(client.is_merging().await.unwrap());
+
+        assert!(client.checkout("feature".to_string(), None, None).await.is_err());
+        assert_eq!(client.repository().read_working_branch().await.unwrap(), Some(branch("main")));
+
+        client.checkout("feature".to_string(), Some(true), None).await.unwrap();
+        assert!(!client.is_merging().await.unwrap());
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"theirs\n".to_vec()));
+    }
+}
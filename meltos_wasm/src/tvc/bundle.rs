@@ -0,0 +1,540 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::io::bundle::{Bundle, BundleBranch, BundleObject, BundleTrace};
+use meltos_tvc::object::commit::{CommitHash, CommitObj};
+use meltos_tvc::object::{AsMeta, Obj, ObjHash};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::abort::AbortSignal;
+use crate::error;
+use crate::error::{classify, CORRUPTED, IntoJsResult, TvcError};
+use crate::js_vec::JsVecU8;
+use crate::tvc::diff::{ADDED, DELETED, MODIFIED};
+use crate::tvc::events::BUNDLE_SYNCED;
+use crate::tvc::repository::{into_tree, Repository, Snapshot};
+use crate::tvc::tag::is_tags_branch;
+use crate::tvc::WasmTvcClient;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BundlePreview {
+    pub branches: Vec<BranchUpdate>,
+    /// The commits which don't exist in the repository yet.
+    pub commits: Vec<String>,
+    pub files: Vec<BundleFileChange>,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BranchUpdate {
+    pub branch: String,
+    /// `undefined` if the branch is created by the bundle.
+    pub old_head: Option<String>,
+    pub new_head: String,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BundleFileChange {
+    pub branch: String,
+    pub path: String,
+    /// One of `added`, `deleted` or `modified`.
+    pub status: String,
+}
+
+
+/// Reads the objects of a bundle, falling back to the objects in the repository.
+struct BundleObjects<'a> {
+    /// The objects in the bundle, expanded once and checked against their hashes.
+    objs: HashMap<&'a ObjHash, Obj>,
+    /// The trace tree of each commit in the bundle.
+    traces: HashMap<&'a CommitHash, &'a ObjHash>,
+    repository: Repository,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Saves the branches, commits and objects of a bundle received from the room.
+    ///
+    /// Fails without saving anything if the bundle isn't valid json, doesn't match the bundle format,
+    /// or refers to objects which are neither in the bundle nor in the repository.
+    pub async fn sync_bundle(&self, bundle: &str, signal: Option<AbortSignal>) -> error::Result {
+        self.observe_heads(Some(BUNDLE_SYNCED), self.abortable(signal, |client| async move {
+            let bundle = parse_bundle(bundle)?;
+            client.verify_bundle(&bundle).await?;
+            client.tvc.save_bundle(bundle).await.map_err(classify)?;
+            client.apply_received_tags().await
+        })).await
+    }
+
+    /// Reports the branches, commits and files which [`WasmTvcClient::sync_bundle`] would change.
+    pub async fn preview_bundle(&self, bundle: &str) -> error::Result<BundlePreview> {
+        let bundle = parse_bundle(bundle)?;
+        let objects = self.verify_bundle(&bundle).await?;
+        self.preview(&bundle, &objects).await
+    }
+
+    /// Writes the commits of the branches and the objects they need into a bundle file,
+    /// which can be exchanged without the server and read by [`WasmTvcClient::import_bundle`].
+    ///
+    /// If `since`, a branch name, a tag name or a commit hash, is given, the commits reachable from it
+    /// and their files are left out, assuming the receiver already has them.
+    /// The bundle is json in the format of [`WasmTvcClient::sync_bundle`], gzipped if `compress` is true.
+    pub async fn export_bundle(
+        &self,
+        branches: Vec<String>,
+        since: Option<String>,
+        compress: Option<bool>,
+        signal: Option<AbortSignal>,
+    ) -> error::Result<JsVecU8> {
+        self.abortable(signal, |client| async move {
+            let repository = client.repository();
+            let since = match since {
+                Some(since) => Some(repository.resolve(&since).await?),
+                None => None,
+            };
+
+            let mut bundle = Bundle {
+                traces: Vec::new(),
+                objs: Vec::new(),
+                branches: Vec::new(),
+            };
+            let mut obj_hashes = BTreeSet::new();
+            let mut traced = HashSet::new();
+            for branch_name in branches {
+                let branch_name = BranchName(branch_name);
+                let Some(head) = repository.read_head(&branch_name).await? else {
+                    return Err(TvcError::not_found("branch", &branch_name).into());
+                };
+                // Only the commits after `since` are listed; the branch is left out if it has none.
+                let commits = repository.commits_between(since.as_ref(), &head).await?;
+                if commits.is_empty() {
+                    continue;
+                }
+                for commit_hash in &commits {
+                    if !traced.insert(commit_hash.clone()) {
+                        continue;
+                    }
+                    let commit = repository.read_commit(commit_hash).await?;
+                    obj_hashes.insert(commit_hash.0.clone());
+                    obj_hashes.insert(commit.committed_objs_tree.0.clone());
+                    obj_hashes.extend(repository.read_tree(&commit.committed_objs_tree).await?.into_values());
+
+                    let snapshot = repository.read_snapshot(commit_hash).await?;
+                    let trace = into_tree(&snapshot).as_meta().into_js_result()?;
+                    bundle.traces.push(BundleTrace {
+                        commit_hash: commit_hash.clone(),
+                        obj_hash: trace.hash.clone(),
+                    });
+                    bundle.objs.push(BundleObject {
+                        hash: trace.hash,
+                        compressed_buf: trace.compressed_buf,
+                    });
+                    // The files changed by the commits are sent with their committed objects,
+                    // and the receiver has the other files if it has `since`.
+                    if since.is_none() {
+                        obj_hashes.extend(snapshot.into_values());
+                    }
+                }
+                bundle.branches.push(BundleBranch {
+                    branch_name,
+                    commits,
+                });
+            }
+
+            for obj_hash in obj_hashes {
+                let Some(compressed_buf) = repository.read_compressed_obj(&obj_hash).await? else {
+                    return Err(TvcError::not_found("object", &obj_hash.0).into());
+                };
+                bundle.objs.push(BundleObject {
+                    hash: obj_hash,
+                    compressed_buf,
+                });
+            }
+
+            let json = serde_json::to_vec(&bundle).map_err(|e| TvcError::new(CORRUPTED, e.to_string()))?;
+            if !compress.unwrap_or(false) {
+                return Ok(JsVecU8(json));
+            }
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&json).into_js_result()?;
+            Ok(JsVecU8(encoder.finish().into_js_result()?))
+        }).await
+    }
+
+    /// Verifies a bundle file written by [`WasmTvcClient::export_bundle`] and saves it.
+    ///
+    /// Returns what the bundle has changed; nothing is saved if the bundle is invalid.
+    pub async fn import_bundle(&self, bytes: Vec<u8>, signal: Option<AbortSignal>) -> error::Result<BundlePreview> {
+        self.observe_heads(Some(BUNDLE_SYNCED), self.abortable(signal, |client| async move {
+            let json = if bytes.starts_with(&GZIP_MAGIC) {
+                let mut json = String::new();
+                GzDecoder::new(bytes.as_slice())
+                    .read_to_string(&mut json)
+                    .map_err(|e| TvcError::new(CORRUPTED, format!("malformed bundle file: {e}")))?;
+                json
+            } else {
+                String::from_utf8(bytes).map_err(|e| TvcError::new(CORRUPTED, format!("malformed bundle file: {e}")))?
+            };
+            let bundle = parse_bundle(&json)?;
+            let objects = client.verify_bundle(&bundle).await?;
+            let preview = client.preview(&bundle, &objects).await?;
+            client.tvc.save_bundle(bundle).await.map_err(classify)?;
+            client.apply_received_tags().await?;
+            Ok(preview)
+        })).await
+    }
+}
+
+
+impl WasmTvcClient {
+    async fn preview(&self, bundle: &Bundle, objects: &BundleObjects<'_>) -> error::Result<BundlePreview> {
+        let repository = self.repository();
+        let mut preview = BundlePreview::default();
+        for bundle_commit in bundle_commits(bundle) {
+            if !repository.exists_commit(bundle_commit).await {
+                preview.commits.push(bundle_commit.0.0.clone());
+            }
+        }
+        for branch in bundle.branches.iter().filter(|branch| !is_tags_branch(&branch.branch_name)) {
+            let Some(new_head) = objects.head_of(&branch.commits).await? else {
+                continue;
+            };
+            let old_head = repository.read_head(&branch.branch_name).await?;
+            if old_head.as_ref() == Some(&new_head) {
+                continue;
+            }
+            let old = match old_head.as_ref() {
+                Some(old_head) => repository.read_snapshot(old_head).await?,
+                None => Snapshot::new(),
+            };
+            let new = objects.read_snapshot(&new_head).await?;
+            preview.files.extend(file_changes(&branch.branch_name, &old, &new));
+            preview.branches.push(BranchUpdate {
+                branch: branch.branch_name.0.clone(),
+                old_head: old_head.map(|head| head.0.0),
+                new_head: new_head.0.0,
+            });
+        }
+        Ok(preview)
+    }
+
+
+    /// Checks that every object the bundle refers to is in the bundle or in the repository,
+    /// and that every object in the bundle can be expanded and matches its hash.
+    async fn verify_bundle<'a>(&self, bundle: &'a Bundle) -> error::Result<BundleObjects<'a>> {
+        let objects = BundleObjects::new(bundle, self.repository())?;
+        for commit_hash in bundle_commits(bundle) {
+            let commit = objects.read_commit(commit_hash).await?;
+            let committed = objects.read_snapshot_tree(&commit.committed_objs_tree.0).await?;
+            objects.ensure_exist(committed.values()).await?;
+        }
+        for trace in &bundle.traces {
+            let snapshot = objects.read_snapshot_tree(&trace.obj_hash).await?;
+            objects.ensure_exist(snapshot.values()).await?;
+        }
+        Ok(objects)
+    }
+}
+
+
+impl<'a> BundleObjects<'a> {
+    fn new(bundle: &'a Bundle, repository: Repository) -> error::Result<Self> {
+        let mut objs = HashMap::with_capacity(bundle.objs.len());
+        for obj in &bundle.objs {
+            let expanded = Obj::expand(&obj.compressed_buf)
+                .map_err(|e| TvcError::new(CORRUPTED, format!("corrupted object in bundle {}: {e}", obj.hash.0)))?;
+            if content_hash(&expanded)? != obj.hash {
+                return Err(TvcError::new(CORRUPTED, format!("object in bundle doesn't match its hash: {}", obj.hash.0)).into());
+            }
+            objs.insert(&obj.hash, expanded);
+        }
+        Ok(Self {
+            objs,
+            traces: bundle
+                .traces
+                .iter()
+                .map(|trace| (&trace.commit_hash, &trace.obj_hash))
+                .collect(),
+            repository,
+        })
+    }
+
+
+    async fn read(&self, obj_hash: &ObjHash) -> error::Result<Obj> {
+        if let Some(obj) = self.objs.get(obj_hash) {
+            return Ok(obj.clone());
+        }
+        self
+            .repository
+            .read_obj(obj_hash)
+            .await?
+            .ok_or_else(|| missing_object(obj_hash))
+    }
+
+
+    async fn ensure_exist(&self, obj_hashes: impl Iterator<Item = &ObjHash>) -> error::Result {
+        for obj_hash in obj_hashes {
+            if !self.objs.contains_key(obj_hash) && self.repository.read_compressed_obj(obj_hash).await?.is_none() {
+                return Err(missing_object(obj_hash));
+            }
+        }
+        Ok(())
+    }
+
+
+    async fn read_commit(&self, commit_hash: &CommitHash) -> error::Result<CommitObj> {
+        match self.read(&commit_hash.0).await? {
+            Obj::Commit(commit) => Ok(commit),
+            _ => Err(TvcError::new(CORRUPTED, format!("not a commit: {}", commit_hash.0.0)).into()),
+        }
+    }
+
+
+    async fn read_snapshot_tree(&self, obj_hash: &ObjHash) -> error::Result<Snapshot> {
+        match self.read(obj_hash).await? {
+            Obj::Tree(tree) => Ok(tree
+                .0
+                .into_iter()
+                .map(|(file_path, obj_hash)| (file_path.0, obj_hash))
+                .collect()),
+            _ => Err(TvcError::new(CORRUPTED, format!("not a tree: {}", obj_hash.0)).into()),
+        }
+    }
+
+
+    /// Reads the files committed at the commit from the trace in the bundle or in the repository.
+    async fn read_snapshot(&self, commit_hash: &CommitHash) -> error::Result<Snapshot> {
+        match self.traces.get(commit_hash) {
+            Some(obj_hash) => self.read_snapshot_tree(obj_hash).await,
+            None => self.repository.read_snapshot(commit_hash).await,
+        }
+    }
+
+
+    /// Finds the commit which isn't a parent of any other of `commits`.
+    async fn head_of(&self, commits: &[CommitHash]) -> error::Result<Option<CommitHash>> {
+        let mut parents = HashMap::new();
+        for commit_hash in commits {
+            parents.insert(commit_hash, self.read_commit(commit_hash).await?.parents);
+        }
+        Ok(commits
+            .iter()
+            .find(|commit_hash| !parents.values().any(|parents| parents.contains(commit_hash)))
+            .cloned())
+    }
+}
+
+
+/// Computes the hash of the object from its content.
+fn content_hash(obj: &Obj) -> error::Result<ObjHash> {
+    let meta = match obj {
+        Obj::File(file) => file.as_meta(),
+        Obj::Delete(delete) => delete.as_meta(),
+        Obj::Tree(tree) => tree.as_meta(),
+        Obj::Commit(commit) => commit.as_meta(),
+    };
+    Ok(meta.into_js_result()?.hash)
+}
+
+
+#[inline]
+fn missing_object(obj_hash: &ObjHash) -> JsValue {
+    TvcError::new(CORRUPTED, format!("bundle refers to a missing object: {}", obj_hash.0)).into()
+}
+
+
+fn parse_bundle(bundle: &str) -> error::Result<Bundle> {
+    let json: serde_json::Value = serde_json::from_str(bundle)
+        .map_err(|e| TvcError::new(CORRUPTED, format!("malformed bundle json: {e}")))?;
+    serde_json::from_value(json).map_err(|e| TvcError::new(
+        CORRUPTED,
+        format!("bundle doesn't match the format: {e}"),
+    ).into())
+}
+
+
+#[inline]
+fn bundle_commits(bundle: &Bundle) -> BTreeSet<&CommitHash> {
+    bundle
+        .branches
+        .iter()
+        .flat_map(|branch| branch.commits.iter())
+        .chain(bundle.traces.iter().map(|trace| &trace.commit_hash))
+        .collect()
+}
+
+
+fn file_changes(branch_name: &BranchName, old: &Snapshot, new: &Snapshot) -> Vec<BundleFileChange> {
+    old
+        .keys()
+        .chain(new.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter_map(|path| {
+            let status = match (old.get(path), new.get(path)) {
+                (Some(old), Some(new)) if old != new => MODIFIED,
+                (Some(_), None) => DELETED,
+                (None, Some(_)) => ADDED,
+                _ => return None,
+            };
+            Some(BundleFileChange {
+                branch: branch_name.0.clone(),
+                path: path.clone(),
+                status: status.to_string(),
+            })
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::io::bundle::Bundle;
+    use meltos_tvc::object::commit::CommitHash;
+    use meltos_tvc::object::ObjHash;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, tvc_client, wasm_fs};
+    use crate::tvc::bundle::parse_bundle;
+    use crate::tvc::WasmTvcClient;
+
+    async fn export_main(client: &WasmTvcClient) -> Bundle {
+        let bytes = client.export_bundle(vec!["main".to_string()], None, None, None).await.unwrap();
+        parse_bundle(&String::from_utf8(bytes.0).unwrap()).unwrap()
+    }
+
+    async fn main_head(client: &WasmTvcClient) -> Option<CommitHash> {
+        client.repository().read_head(&BranchName("main".to_string())).await.unwrap()
+    }
+
+    async fn file_obj(client: &WasmTvcClient, path: &str) -> ObjHash {
+        let head = main_head(client).await.unwrap();
+        client.repository().read_snapshot(&head).await.unwrap().remove(path).unwrap()
+    }
+
+    async fn assert_same_main(source: &WasmTvcClient, client: &WasmTvcClient) {
+        let head = main_head(source).await.unwrap();
+        assert_eq!(main_head(client).await, Some(head.clone()));
+        let snapshot = source.repository().read_snapshot(&head).await.unwrap();
+        assert_eq!(client.repository().read_snapshot(&head).await.unwrap(), snapshot);
+        for obj_hash in snapshot.values() {
+            assert_eq!(
+                client.repository().read_file_obj(obj_hash).await.unwrap(),
+                source.repository().read_file_obj(obj_hash).await.unwrap(),
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn malformed_json() {
+        assert!(parse_bundle("{ \"traces\": [").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    fn schema_mismatch() {
+        assert!(parse_bundle("{ \"unknown\": 1 }").is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn valid_bundle() {
+        let source = tvc_client("bundle_valid_source").await;
+        commit_files(&source, "main", &[("workspace/a.txt", "a")]).await;
+        let bundle = export_main(&source).await;
+
+        let client = tvc_client("bundle_valid").await;
+        assert!(client.verify_bundle(&bundle).await.is_ok());
+    }
+
+    #[wasm_bindgen_test]
+    async fn reject_missing_object() {
+        let source = tvc_client("bundle_missing_source").await;
+        commit_files(&source, "main", &[("workspace/a.txt", "a")]).await;
+        let mut bundle = export_main(&source).await;
+        let missing = file_obj(&source, "workspace/a.txt").await;
+        bundle.objs.retain(|obj| obj.hash != missing);
+
+        let client = tvc_client("bundle_missing").await;
+        assert!(client.verify_bundle(&bundle).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn reject_tampered_object() {
+        let source = tvc_client("bundle_tampered_source").await;
+        commit_files(&source, "main", &[("workspace/a.txt", "a"), ("workspace/b.txt", "b")]).await;
+        let mut bundle = export_main(&source).await;
+        let a = file_obj(&source, "workspace/a.txt").await;
+        let b = file_obj(&source, "workspace/b.txt").await;
+        let b_buf = bundle.objs.iter().find(|obj| obj.hash == b).unwrap().compressed_buf.clone();
+        bundle.objs.iter_mut().find(|obj| obj.hash == a).unwrap().compressed_buf = b_buf;
+
+        let client = tvc_client("bundle_tampered").await;
+        assert!(client.verify_bundle(&bundle).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn round_trip() {
+        let source = tvc_client("bundle_round_trip_source").await;
+        commit_files(&source, "main", &[("workspace/a.txt", "a"), ("workspace/b.txt", "b")]).await;
+        commit_files(&source, "main", &[("workspace/a.txt", "a2")]).await;
+        let bytes = source.export_bundle(vec!["main".to_string()], None, Some(true), None).await.unwrap();
+
+        let client = WasmTvcClient::new(&wasm_fs("bundle_round_trip").await);
+        let preview = client.import_bundle(bytes.0, None).await.unwrap();
+        assert_eq!(preview.commits.len(), 3);
+        assert_same_main(&source, &client).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn incremental_round_trip() {
+        let source = tvc_client("bundle_incremental_source").await;
+        let since = commit_files(&source, "main", &[("workspace/a.txt", "a"), ("workspace/b.txt", "b")]).await;
+        let full = source.export_bundle(vec!["main".to_string()], None, None, None).await.unwrap();
+        commit_files(&source, "main", &[("workspace/a.txt", "a2")]).await;
+        commit_files(&source, "main", &[("workspace/c.txt", "c")]).await;
+        let incremental = source
+            .export_bundle(vec!["main".to_string()], Some(since.0.0.clone()), None, None)
+            .await
+            .unwrap();
+        let bundle = parse_bundle(&String::from_utf8(incremental.0.clone()).unwrap()).unwrap();
+        assert_eq!(bundle.branches[0].commits.len(), 2);
+        assert!(!bundle.objs.iter().any(|obj| obj.hash == since.0));
+
+        let client = WasmTvcClient::new(&wasm_fs("bundle_incremental").await);
+        client.import_bundle(full.0, None).await.unwrap();
+        assert_eq!(main_head(&client).await, Some(since.clone()));
+        let preview = client.import_bundle(incremental.0, None).await.unwrap();
+        assert_eq!(preview.commits.len(), 2);
+        assert_eq!(preview.branches[0].old_head, Some(since.0.0));
+        assert_same_main(&source, &client).await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn incremental_bundle_needs_since() {
+        let source = tvc_client("bundle_needs_since_source").await;
+        let since = commit_files(&source, "main", &[("workspace/a.txt", "a")]).await;
+        commit_files(&source, "main", &[("workspace/a.txt", "a2")]).await;
+        let incremental = source
+            .export_bundle(vec!["main".to_string()], Some(since.0.0), None, None)
+            .await
+            .unwrap();
+
+        let client = WasmTvcClient::new(&wasm_fs("bundle_needs_since").await);
+        assert!(client.import_bundle(incremental.0, None).await.is_err());
+        assert_eq!(main_head(&client).await, None);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::BTreeSet;
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use meltos_tvc::object::commit::CommitHash;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{IntoJsResult, TvcError};
+use crate::file_system::path::{find_collisions, PathCollision};
+use crate::tvc::WasmTvcClient;
+
+/// How `stage` and `unzip` treat paths which clobber each other
+/// on a case-insensitive or normalizing file system.
+#[wasm_bindgen]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum CollisionPolicy {
+    /// Fails with a `Conflict` error.
+    #[default]
+    Error,
+    /// Goes on and returns the collisions to the caller.
+    Warn,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    #[inline(always)]
+    pub fn set_collision_policy(&mut self, policy: CollisionPolicy) {
+        self.collision_policy = policy;
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Checks collisions between the files to be staged and
+    /// the other files staged or committed on the branch.
+    ///
+    /// Returns the collisions found under the warn policy.
+    pub(crate) async fn check_stage_collisions(
+        &self,
+        branch_name: &BranchName,
+        path: &str,
+    ) -> error::Result<Vec<PathCollision>> {
+        let staging: BTreeSet<String> = self.fs.all_files_in(path).await.into_js_result()?.into_iter().collect();
+        let working = self.working_tree().await?;
+        // A file which no longer exists in the workspace is being deleted or renamed away.
+        let remaining = self
+            .staged_tree(branch_name)
+            .await?
+            .into_keys()
+            .filter(|path| working.contains_key(path));
+        self.check_collisions(stage_collisions(&staging, remaining))
+    }
+
+
+    /// Checks collisions between the files committed at the commit.
+    ///
+    /// Returns the collisions found under the warn policy.
+    pub(crate) async fn check_unzip_collisions(&self, commit_hash: &CommitHash) -> error::Result<Vec<PathCollision>> {
+        let snapshot = self.repository().read_snapshot(commit_hash).await?;
+        self.check_collisions(find_collisions(snapshot.keys().map(String::as_str)))
+    }
+
+
+    fn check_collisions(&self, collisions: Vec<PathCollision>) -> error::Result<Vec<PathCollision>> {
+        if collisions.is_empty() || self.collision_policy == CollisionPolicy::Warn {
+            return Ok(collisions);
+        }
+
+        let message = collision_message(&collisions);
+        let paths = collisions.iter().flat_map(|collision| collision.paths.iter().map(String::as_str));
+        Err(TvcError::conflict(message, paths).into())
+    }
+}
+
+
+/// Finds the collisions which involve any of the files to be staged,
+/// leaving the ones only between the `remaining` files to be reported when they are staged.
+fn stage_collisions(staging: &BTreeSet<String>, remaining: impl Iterator<Item = String>) -> Vec<PathCollision> {
+    let paths: BTreeSet<String> = staging.iter().cloned().chain(remaining).collect();
+    find_collisions(paths.iter().map(String::as_str))
+        .into_iter()
+        .filter(|collision| collision.paths.iter().any(|path| staging.contains(path)))
+        .collect()
+}
+
+
+fn collision_message(collisions: &[PathCollision]) -> String {
+    let groups: Vec<String> = collisions
+        .iter()
+        .map(|collision| collision.paths.join(", "))
+        .collect();
+    format!("path collisions detected: [{}]", groups.join("], ["))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tvc::collision::stage_collisions;
+
+    fn paths(paths: &[&str]) -> BTreeSet<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn collide_with_remaining_file() {
+        let collisions = stage_collisions(&paths(&["workspace/Readme.md"]), paths(&["workspace/README.md"]).into_iter());
+        assert_eq!(collisions.len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn case_only_rename_is_not_collision() {
+        // README.md is no longer in the workspace, so it isn't in the remaining files.
+        let collisions = stage_collisions(&paths(&["workspace/Readme.md"]), paths(&["workspace/src/main.rs"]).into_iter());
+        assert!(collisions.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn ignore_collisions_between_other_files() {
+        let remaining = paths(&["workspace/a.txt", "workspace/A.txt"]);
+        assert!(stage_collisions(&paths(&["workspace/b.txt"]), remaining.clone().into_iter()).is_empty());
+        assert_eq!(stage_collisions(&paths(&["workspace/a.txt"]), remaining.into_iter()).len(), 1);
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::BTreeSet;
+
+use meltos_tvc::branch::BranchName;
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, ChangeTag, TextDiff};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{INVALID_OPERATION, TvcError};
+use crate::js_vec::JsVecFileDiff;
+use crate::tvc::WasmTvcClient;
+use crate::tvc::worktree::{is_under, Tree};
+
+const DEFAULT_CONTEXT: usize = 3;
+
+/// Deleted and added files at least this similar are reported as a rename.
+const RENAME_SIMILARITY: f32 = 0.5;
+
+pub const ADDED: &str = "added";
+pub const DELETED: &str = "deleted";
+pub const MODIFIED: &str = "modified";
+pub const RENAMED: &str = "renamed";
+
+pub const CONTEXT: &str = "context";
+pub const INSERT: &str = "insert";
+pub const DELETE: &str = "delete";
+
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DiffOptions {
+    /// The number of unchanged lines around each hunk, 3 by default.
+    pub context: Option<usize>,
+    pub ignore_whitespace: Option<bool>,
+    pub detect_renames: Option<bool>,
+    /// Diffs only this file or the files under this directory.
+    pub path: Option<String>,
+}
+
+
+impl DiffOptions {
+    pub fn from_js(options: JsValue) -> error::Result<Self> {
+        if options.is_undefined() || options.is_null() {
+            Ok(Self::default())
+        } else {
+            Ok(serde_wasm_bindgen::from_value(options).map_err(|e| TvcError::new(INVALID_OPERATION, e.to_string()))?)
+        }
+    }
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub old_path: Option<String>,
+    pub new_path: Option<String>,
+    /// One of `added`, `deleted`, `modified` or `renamed`.
+    pub status: String,
+    pub binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// One of `context`, `insert` or `delete`.
+    pub kind: String,
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    /// Whether this is the last line of its side and has no line ending,
+    /// which unified diff marks with `\ No newline at end of file`.
+    #[serde(default)]
+    pub no_newline: bool,
+}
+
+
+#[wasm_bindgen]
+impl DiffHunk {
+    /// The unified diff header of the hunk such as `@@ -1,3 +1,4 @@`.
+    pub fn header(&self) -> String {
+        format!(
+            "@@ -{},{} +{},{} @@",
+            self.old_start, self.old_lines, self.new_start, self.new_lines
+        )
+    }
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Diffs the working files against the head of the branch.
+    pub async fn diff_working(&self, branch_name: String, options: JsValue) -> error::Result<JsVecFileDiff> {
+        let options = DiffOptions::from_js(options)?;
+        let branch_name = BranchName(branch_name);
+        let old = self.head_tree(&branch_name).await?;
+        let new = self.working_tree().await?;
+        Ok(JsVecFileDiff(self.diff_trees(&old, &new, &options).await?))
+    }
+
+    /// Diffs the staged files against the head of the branch.
+    pub async fn diff_staged(&self, branch_name: String, options: JsValue) -> error::Result<JsVecFileDiff> {
+        let options = DiffOptions::from_js(options)?;
+        let branch_name = BranchName(branch_name);
+        let old = self.head_tree(&branch_name).await?;
+        let new = self.staged_tree(&branch_name).await?;
+        Ok(JsVecFileDiff(self.diff_trees(&old, &new, &options).await?))
+    }
+
+    /// Diffs the files committed at `to` against the files committed at `from`.
+    pub async fn diff_commits(&self, from: String, to: String, options: JsValue) -> error::Result<JsVecFileDiff> {
+        let options = DiffOptions::from_js(options)?;
+        let old = self.commit_tree(&from).await?;
+        let new = self.commit_tree(&to).await?;
+        Ok(JsVecFileDiff(self.diff_trees(&old, &new, &options).await?))
+    }
+}
+
+
+impl WasmTvcClient {
+    pub(crate) async fn diff_trees(
+        &self,
+        old: &Tree,
+        new: &Tree,
+        options: &DiffOptions,
+    ) -> error::Result<Vec<FileDiff>> {
+        let in_scope = |path: &&String| options
+            .path
+            .as_deref()
+            .is_none_or(|scope| is_under(path, scope));
+        let paths: BTreeSet<&String> = old.keys().chain(new.keys()).filter(in_scope).collect();
+
+        let mut deleted = Vec::new();
+        let mut added = Vec::new();
+        let mut diffs = Vec::new();
+        for path in paths {
+            match (old.get(path), new.get(path)) {
+                (Some(old_blob), Some(new_blob)) => {
+                    if self.is_same_blob(old_blob, new_blob).await? {
+                        continue;
+                    }
+                    let old_buf = self.read_blob(old_blob).await?;
+                    let new_buf = self.read_blob(new_blob).await?;
+                    diffs.push(diff_file(Some(path), Some(path), MODIFIED, &old_buf, &new_buf, options));
+                }
+                (Some(old_blob), None) => deleted.push((path, self.read_blob(old_blob).await?)),
+                (None, Some(new_blob)) => added.push((path, self.read_blob(new_blob).await?)),
+                (None, None) => {}
+            }
+        }
+
+        if options.detect_renames.unwrap_or(false) {
+            for (old_path, old_buf) in std::mem::take(&mut deleted) {
+                match find_rename(&old_buf, &added) {
+                    Some(i) => {
+                        let (new_path, new_buf) = added.remove(i);
+                        diffs.push(diff_file(Some(old_path), Some(new_path), RENAMED, &old_buf, &new_buf, options));
+                    }
+                    None => deleted.push((old_path, old_buf)),
+                }
+            }
+        }
+        for (path, buf) in deleted {
+            diffs.push(diff_file(Some(path), None, DELETED, &buf, &[], options));
+        }
+        for (path, buf) in added {
+            diffs.push(diff_file(None, Some(path), ADDED, &[], &buf, options));
+        }
+        Ok(diffs)
+    }
+}
+
+
+fn find_rename(old_buf: &[u8], added: &[(&String, Vec<u8>)]) -> Option<usize> {
+    if let Some(i) = added.iter().position(|(_, new_buf)| new_buf == old_buf) {
+        return Some(i);
+    }
+    let old_text = std::str::from_utf8(old_buf).ok()?;
+    added
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (_, new_buf))| {
+            let new_text = std::str::from_utf8(new_buf).ok()?;
+            let ratio = TextDiff::from_lines(old_text, new_text).ratio();
+            (RENAME_SIMILARITY <= ratio).then_some((i, ratio))
+        })
+        .max_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs))
+        .map(|(i, _)| i)
+}
+
+
+fn diff_file(
+    old_path: Option<&String>,
+    new_path: Option<&String>,
+    status: &str,
+    old_buf: &[u8],
+    new_buf: &[u8],
+    options: &DiffOptions,
+) -> FileDiff {
+    let text = std::str::from_utf8(old_buf)
+        .ok()
+        .zip(std::str::from_utf8(new_buf).ok())
+        .filter(|(old, new)| !old.contains('\0') && !new.contains('\0'));
+    FileDiff {
+        old_path: old_path.cloned(),
+        new_path: new_path.cloned(),
+        status: status.to_string(),
+        binary: text.is_none(),
+        hunks: text
+            .map(|(old, new)| diff_text(
+                old,
+                new,
+                options.context.unwrap_or(DEFAULT_CONTEXT),
+                options.ignore_whitespace.unwrap_or(false),
+            ))
+            .unwrap_or_default(),
+    }
+}
+
+
+/// Diffs two texts line by line into unified diff hunks.
+///
+/// The lines keep their line endings while compared,
+/// so that adding or removing the line ending at the end of the file is a change.
+pub fn diff_text(old: &str, new: &str, context: usize, ignore_whitespace: bool) -> Vec<DiffHunk> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let key = |line: &&str| if ignore_whitespace {
+        line.chars().filter(|c| !c.is_whitespace()).collect()
+    } else {
+        line.to_string()
+    };
+    let old_keys: Vec<String> = old_lines.iter().map(key).collect();
+    let new_keys: Vec<String> = new_lines.iter().map(key).collect();
+
+    let ops = similar::capture_diff_slices(Algorithm::Myers, &old_keys, &new_keys);
+    similar::group_diff_ops(ops, context)
+        .into_iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+            let last = group.last()?;
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+
+            let mut lines = Vec::new();
+            for op in &group {
+                for change in op.iter_changes(&old_lines, &new_lines) {
+                    lines.push(DiffLine {
+                        kind: match change.tag() {
+                            ChangeTag::Equal => CONTEXT,
+                            ChangeTag::Insert => INSERT,
+                            ChangeTag::Delete => DELETE,
+                        }.to_string(),
+                        content: strip_line_ending(change.value()).to_string(),
+                        old_line: change.old_index().map(|i| i as u32 + 1),
+                        new_line: change.new_index().map(|i| i as u32 + 1),
+                        no_newline: !change.value().ends_with('\n'),
+                    });
+                }
+            }
+            Some(DiffHunk {
+                old_start: hunk_start(&old_range),
+                old_lines: old_range.len() as u32,
+                new_start: hunk_start(&new_range),
+                new_lines: new_range.len() as u32,
+                lines,
+            })
+        })
+        .collect()
+}
+
+
+#[inline]
+pub(crate) fn strip_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+
+/// The start line of a hunk as written in unified diff, where an empty range
+/// points to the line just before it.
+#[inline]
+fn hunk_start(range: &std::ops::Range<usize>) -> u32 {
+    if range.is_empty() {
+        range.start as u32
+    } else {
+        range.start as u32 + 1
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tvc::diff::{DELETE, diff_text, INSERT};
+
+    #[wasm_bindgen_test]
+    fn no_hunks_if_same() {
+        assert!(diff_text("a\nb\n", "a\nb\n", 3, false).is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    fn modified_line() {
+        let hunks = diff_text("a\nb\nc\n", "a\nB\nc\n", 3, false);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].header(), "@@ -1,3 +1,3 @@");
+        let changed: Vec<(&str, &str)> = hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.kind != "context")
+            .map(|line| (line.kind.as_str(), line.content.as_str()))
+            .collect();
+        assert_eq!(changed, vec![(DELETE, "b"), (INSERT, "B")]);
+    }
+
+    #[wasm_bindgen_test]
+    fn split_distant_changes_into_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        assert_eq!(diff_text(old, new, 1, false).len(), 2);
+        assert_eq!(diff_text(old, new, 5, false).len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn ignore_whitespace() {
+        assert!(diff_text("fn main() {}\n", "fn  main()  {}\n", 3, true).is_empty());
+        assert_eq!(diff_text("fn main() {}\n", "fn  main()  {}\n", 3, false).len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    fn add_newline_at_end_of_file() {
+        let hunks = diff_text("a\nb", "a\nb\n", 3, false);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].header(), "@@ -1,2 +1,2 @@");
+        let changed: Vec<(&str, &str, bool)> = hunks[0]
+            .lines
+            .iter()
+            .filter(|line| line.kind != "context")
+            .map(|line| (line.kind.as_str(), line.content.as_str(), line.no_newline))
+            .collect();
+        assert_eq!(changed, vec![(DELETE, "b", true), (INSERT, "b", false)]);
+        assert!(!hunks[0].lines[0].no_newline);
+    }
+
+    #[wasm_bindgen_test]
+    fn remove_newline_at_end_of_file() {
+        let hunks = diff_text("a\n", "a", 3, false);
+        assert_eq!(hunks.len(), 1);
+        assert!(hunks[0].lines.iter().any(|line| line.kind == INSERT && line.no_newline));
+    }
+}
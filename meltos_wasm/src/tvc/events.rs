@@ -0,0 +1,377 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::rc::Rc;
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::object::commit::CommitHash;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_futures::js_sys::Function;
+
+use crate::error;
+use crate::error::{INVALID_OPERATION, TvcError};
+use crate::tvc::bundle::BranchUpdate;
+use crate::tvc::merge;
+use crate::tvc::merge::MergeResult;
+use crate::tvc::tag::is_tags_branch;
+use crate::tvc::WasmTvcClient;
+
+pub const STAGED: &str = "staged";
+pub const UNSTAGED: &str = "unstaged";
+pub const COMMITTED: &str = "committed";
+pub const PUSHED: &str = "pushed";
+pub const FETCHED: &str = "fetched";
+pub const MERGED: &str = "merged";
+pub const BRANCH_CREATED: &str = "branchCreated";
+pub const HEAD_CHANGED: &str = "headChanged";
+pub const BUNDLE_SYNCED: &str = "bundleSynced";
+
+const EVENTS: [&str; 9] = [
+    STAGED,
+    UNSTAGED,
+    COMMITTED,
+    PUSHED,
+    FETCHED,
+    MERGED,
+    BRANCH_CREATED,
+    HEAD_CHANGED,
+    BUNDLE_SYNCED,
+];
+
+
+#[wasm_bindgen(module = "/js/reportError.js")]
+extern {
+    /// Rethrows `error` outside of the operation, as an exception thrown by an event listener is in the DOM.
+    fn report_error(error: JsValue);
+}
+
+
+/// Passed to the callbacks subscribed by [`WasmTvcClient::on`].
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RepositoryEvent {
+    pub name: String,
+    /// The branch staged, unstaged, committed or merged into, or whose head moved.
+    pub branch: Option<String>,
+    /// The files staged or unstaged.
+    pub paths: Vec<String>,
+    /// The commit created by `committed` and `merged`.
+    pub commit: Option<String>,
+    /// The branches created or moved; the branches pushed with `old_head` undefined for `pushed`.
+    pub branches: Vec<BranchUpdate>,
+}
+
+
+impl RepositoryEvent {
+    #[inline]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+
+/// The callbacks subscribed to each event, shared by the clones of the client.
+#[derive(Debug, Clone, Default)]
+pub struct Listeners(Rc<RefCell<HashMap<String, Vec<Function>>>>);
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Calls `callback` with a [`RepositoryEvent`] each time `event_name` occurs.
+    ///
+    /// The events are `staged`, `unstaged`, `committed`, `pushed`, `fetched`, `merged`,
+    /// `branchCreated`, `headChanged` and `bundleSynced`.
+    /// An exception thrown by `callback` doesn't fail the operation; it is rethrown as an uncaught error.
+    pub fn on(&self, event_name: String, callback: Function) -> error::Result {
+        validate_event_name(&event_name)?;
+        self.listeners
+            .0
+            .borrow_mut()
+            .entry(event_name)
+            .or_default()
+            .push(callback);
+        Ok(())
+    }
+
+    /// Unsubscribes `callback` from `event_name`, or every callback of it if `callback` is omitted.
+    pub fn off(&self, event_name: String, callback: Option<Function>) -> error::Result {
+        validate_event_name(&event_name)?;
+        let mut listeners = self.listeners.0.borrow_mut();
+        match callback {
+            Some(callback) => {
+                if let Some(callbacks) = listeners.get_mut(&event_name) {
+                    callbacks.retain(|subscribed| subscribed != &callback);
+                }
+            }
+            None => {
+                listeners.remove(&event_name);
+            }
+        }
+        Ok(())
+    }
+}
+
+
+impl WasmTvcClient {
+    #[inline]
+    pub(crate) fn is_listened(&self, event_name: &str) -> bool {
+        self.listeners
+            .0
+            .borrow()
+            .get(event_name)
+            .is_some_and(|callbacks| !callbacks.is_empty())
+    }
+
+
+    pub(crate) fn emit(&self, event: RepositoryEvent) {
+        // Cloned so that the callbacks can subscribe or unsubscribe while being called.
+        let Some(callbacks) = self.listeners.0.borrow().get(&event.name).cloned() else {
+            return;
+        };
+        for callback in callbacks {
+            if let Err(e) = callback.call1(&JsValue::NULL, &event.clone().into()) {
+                report_error(e);
+            }
+        }
+    }
+
+
+    #[inline]
+    pub(crate) fn emit_commit(&self, event_name: &str, branch_name: &BranchName, commit_hash: &CommitHash) {
+        self.emit(RepositoryEvent {
+            branch: Some(branch_name.0.clone()),
+            commit: Some(commit_hash.0.0.clone()),
+            ..RepositoryEvent::new(event_name)
+        });
+    }
+
+
+    /// Emits `event_name` with the commit if `result` created one or fast-forwarded the branch.
+    pub(crate) fn emit_merge_result(&self, event_name: &str, branch_name: &BranchName, result: &MergeResult) {
+        if result.kind != merge::MERGED && result.kind != merge::FAST_FORWARD {
+            return;
+        }
+        if let Some(commit) = result.commit.as_ref() {
+            self.emit(RepositoryEvent {
+                branch: Some(branch_name.0.clone()),
+                commit: Some(commit.clone()),
+                ..RepositoryEvent::new(event_name)
+            });
+        }
+    }
+
+
+    /// Runs `operation`, then emits `branchCreated` and `headChanged`
+    /// for the branches it created or moved and for the branch it checked out.
+    ///
+    /// `event` such as `fetched` is also emitted with all of them if given.
+    pub(crate) async fn observe_heads<T>(
+        &self,
+        event: Option<&str>,
+        operation: impl Future<Output = error::Result<T>>,
+    ) -> error::Result<T> {
+        let listened = self.is_listened(BRANCH_CREATED)
+            || self.is_listened(HEAD_CHANGED)
+            || event.is_some_and(|event| self.is_listened(event));
+        if !listened {
+            return operation.await;
+        }
+        let (old_heads, old_working) = self.heads().await?;
+        let out = operation.await?;
+        let (new_heads, new_working) = self.heads().await?;
+
+        let mut updates = Vec::new();
+        for (branch, new_head) in &new_heads {
+            let old_head = old_heads.get(branch);
+            if old_head == Some(new_head) {
+                continue;
+            }
+            let update = BranchUpdate {
+                branch: branch.clone(),
+                old_head: old_head.cloned(),
+                new_head: new_head.clone(),
+            };
+            self.emit(RepositoryEvent {
+                branch: Some(branch.clone()),
+                branches: vec![update.clone()],
+                ..RepositoryEvent::new(if old_head.is_some() { HEAD_CHANGED } else { BRANCH_CREATED })
+            });
+            updates.push(update);
+        }
+        if let Some(working) = new_working.filter(|working| old_working.as_ref() != Some(working)) {
+            if let Some(new_head) = new_heads.get(&working) {
+                self.emit(RepositoryEvent {
+                    branch: Some(working.clone()),
+                    branches: vec![BranchUpdate {
+                        branch: working,
+                        old_head: old_working.and_then(|old_working| old_heads.get(&old_working).cloned()),
+                        new_head: new_head.clone(),
+                    }],
+                    ..RepositoryEvent::new(HEAD_CHANGED)
+                });
+            }
+        }
+
+        if let Some(event) = event {
+            self.emit(RepositoryEvent {
+                branches: updates,
+                ..RepositoryEvent::new(event)
+            });
+        }
+        Ok(out)
+    }
+
+
+    /// Reads the head of each branch and the branch checked out.
+    pub(crate) async fn heads(&self) -> error::Result<(BTreeMap<String, String>, Option<String>)> {
+        let repository = self.repository();
+        // There are no branches before the repository is initialized.
+        let branch_names = self.tvc.branch_names().await.unwrap_or_default();
+        let mut heads = BTreeMap::new();
+        for branch_name in branch_names.into_iter().filter(|branch_name| !is_tags_branch(branch_name)) {
+            if let Some(head) = repository.read_head(&branch_name).await? {
+                heads.insert(branch_name.0, head.0.0);
+            }
+        }
+        let working = repository
+            .read_working_branch()
+            .await?
+            .map(|branch_name| branch_name.0);
+        Ok((heads, working))
+    }
+}
+
+
+fn validate_event_name(event_name: &str) -> error::Result {
+    if EVENTS.contains(&event_name) {
+        Ok(())
+    } else {
+        Err(TvcError::new(INVALID_OPERATION, format!("unknown event: {event_name}")).into())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use wasm_bindgen::closure::Closure;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::js_sys::Function;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, tvc_client, write_files};
+    use crate::tvc::events::{
+        BRANCH_CREATED, COMMITTED, EVENTS, HEAD_CHANGED, MERGED, RepositoryEvent, STAGED, UNSTAGED,
+    };
+    use crate::tvc::WasmTvcClient;
+
+    type Recorded = Rc<RefCell<Vec<RepositoryEvent>>>;
+
+    fn recorder() -> (Function, Recorded) {
+        let events = Recorded::default();
+        let recorded = events.clone();
+        let callback = Closure::<dyn FnMut(RepositoryEvent)>::new(move |event| recorded.borrow_mut().push(event));
+        let function = callback.as_ref().unchecked_ref::<Function>().clone();
+        callback.forget();
+        (function, events)
+    }
+
+    /// Subscribes to every event and returns the events emitted.
+    fn record_all(client: &WasmTvcClient) -> Recorded {
+        let (callback, events) = recorder();
+        for event_name in EVENTS {
+            client.on(event_name.to_string(), callback.clone()).unwrap();
+        }
+        events
+    }
+
+    /// Takes the names of the events emitted so far.
+    fn take_names(events: &Recorded) -> Vec<String> {
+        events.borrow_mut().drain(..).map(|event| event.name).collect()
+    }
+
+    #[wasm_bindgen_test]
+    async fn on_and_off() {
+        let client = tvc_client("events_on_off").await;
+        let (first, first_events) = recorder();
+        let (second, second_events) = recorder();
+        client.on(STAGED.to_string(), first.clone()).unwrap();
+        client.on(STAGED.to_string(), second).unwrap();
+        assert!(client.on("unknown".to_string(), first.clone()).is_err());
+
+        write_files(&client, &[("workspace/a.txt", "a")]).await;
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+        assert_eq!(first_events.borrow().len(), 1);
+        assert_eq!(second_events.borrow().len(), 1);
+
+        client.off(STAGED.to_string(), Some(first)).unwrap();
+        write_files(&client, &[("workspace/b.txt", "b")]).await;
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+        assert_eq!(first_events.borrow().len(), 1);
+        assert_eq!(second_events.borrow().len(), 2);
+
+        client.off(STAGED.to_string(), None).unwrap();
+        write_files(&client, &[("workspace/c.txt", "c")]).await;
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+        assert_eq!(second_events.borrow().len(), 2);
+    }
+
+    #[wasm_bindgen_test]
+    async fn stage_and_un_stage() {
+        let client = tvc_client("events_stage").await;
+        let events = record_all(&client);
+        write_files(&client, &[("workspace/a.txt", "a"), ("workspace/b.txt", "b")]).await;
+
+        client.stage("main".to_string(), "workspace/a.txt".to_string()).await.unwrap();
+        client.un_stage("workspace/a.txt").await.unwrap();
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+        client.un_stage_all().await.unwrap();
+
+        let events: Vec<RepositoryEvent> = events.borrow_mut().drain(..).collect();
+        let names: Vec<&str> = events.iter().map(|event| event.name.as_str()).collect();
+        assert_eq!(names, vec![STAGED, UNSTAGED, STAGED, UNSTAGED]);
+        assert!(events.iter().all(|event| event.branch.as_deref() == Some("main")));
+        assert_eq!(events[1].paths, vec!["workspace/a.txt"]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn commit_branch_checkout_and_merge() {
+        let client = tvc_client("events_operations").await;
+        let events = record_all(&client);
+
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        assert_eq!(take_names(&events), vec![STAGED, HEAD_CHANGED, COMMITTED]);
+
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        let created = events.borrow()[0].clone();
+        assert_eq!(take_names(&events), vec![BRANCH_CREATED]);
+        assert_eq!(created.branch.as_deref(), Some("feature"));
+        assert_eq!(created.branches[0].new_head, head.0.0);
+
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        assert_eq!(take_names(&events), vec![HEAD_CHANGED]);
+
+        commit_files(&client, "feature", &[("workspace/b.txt", "b")]).await;
+        take_names(&events);
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        take_names(&events);
+        client.merge("main".to_string(), "feature".to_string(), None, None, None).await.unwrap();
+        assert_eq!(take_names(&events), vec![MERGED, HEAD_CHANGED]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn only_listened_events_are_emitted() {
+        let client = tvc_client("events_unlistened").await;
+        let (callback, events) = recorder();
+        client.on(STAGED.to_string(), callback).unwrap();
+        commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        assert_eq!(take_names(&events), vec![STAGED]);
+    }
+}
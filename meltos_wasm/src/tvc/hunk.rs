@@ -0,0 +1,605 @@
+use std::ops::Range;
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffTag};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{INVALID_OPERATION, IntoJsResult, TvcError};
+use crate::file_system::path::normalize_path;
+use crate::js_vec::JsVecPathCollision;
+use crate::tvc::diff::{CONTEXT, DELETE, DiffHunk, DiffLine, INSERT, strip_line_ending};
+use crate::tvc::events::{RepositoryEvent, STAGED, UNSTAGED};
+use crate::tvc::WasmTvcClient;
+
+
+/// The changes of a file to stage or unstage.
+///
+/// Exactly one of `hunks`, `ranges` or `patch` is given.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HunkSelection {
+    /// Hunks returned by the diff API; the lines they cover on the new side are selected.
+    pub hunks: Option<Vec<DiffHunk>>,
+    /// Lines of the new side, the working file when staging and the staged file when unstaging.
+    pub ranges: Option<Vec<LineRange>>,
+    /// A unified diff applied as it is.
+    pub patch: Option<String>,
+}
+
+
+/// The lines from `start` to `end` inclusive, counted from 1.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+
+/// The hunks of a unified diff.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct Patch {
+    pub hunks: Vec<DiffHunk>,
+    /// Whether the old and the new file end without a line ending,
+    /// as marked by `\ No newline at end of file`.
+    pub missing_newline: (bool, bool),
+}
+
+
+impl Patch {
+    /// Creates the patch, reading whether either side misses the last line ending from the lines of the hunks.
+    pub fn new(hunks: Vec<DiffHunk>) -> Self {
+        let lines = || hunks.iter().flat_map(|hunk| &hunk.lines).filter(|line| line.no_newline);
+        let missing_newline = (
+            lines().any(|line| line.kind != INSERT),
+            lines().any(|line| line.kind != DELETE),
+        );
+        Self {
+            hunks,
+            missing_newline,
+        }
+    }
+
+
+    /// Swaps the sides of the patch, so that applying it reverts the original patch.
+    pub fn reverse(&self) -> Self {
+        Self {
+            hunks: self.hunks.iter().map(reverse_hunk).collect(),
+            missing_newline: (self.missing_newline.1, self.missing_newline.0),
+        }
+    }
+}
+
+
+impl HunkSelection {
+    pub fn from_js(selection: JsValue) -> error::Result<Self> {
+        let selection: Self = serde_wasm_bindgen::from_value(selection)
+            .map_err(|e| TvcError::new(INVALID_OPERATION, e.to_string()))?;
+        let given = [selection.hunks.is_some(), selection.ranges.is_some(), selection.patch.is_some()];
+        if given.iter().filter(|given| **given).count() != 1 {
+            return Err(TvcError::new(INVALID_OPERATION, "give exactly one of hunks, ranges or patch").into());
+        }
+        // Lines are counted from 1; only a hunk without lines on the new side starts at 0.
+        if let Some(hunk) = selection.hunks.iter().flatten().find(|hunk| hunk.new_start == 0 && hunk.new_lines != 0) {
+            return Err(TvcError::new(INVALID_OPERATION, format!("malformed hunk: {}", hunk.header())).into());
+        }
+        Ok(selection)
+    }
+
+
+    /// The selected lines of the new side as 0-based ranges.
+    fn selected_lines(&self) -> Vec<Range<usize>> {
+        let hunks = self.hunks.iter().flatten().map(|hunk| {
+            let start = hunk.new_start as usize;
+            if hunk.new_lines == 0 {
+                // The hunk only deletes lines, just after its start line.
+                start..start
+            } else {
+                start - 1..start - 1 + hunk.new_lines as usize
+            }
+        });
+        let ranges = self
+            .ranges
+            .iter()
+            .flatten()
+            .map(|range| range.start.saturating_sub(1) as usize..range.end as usize);
+        hunks.chain(ranges).collect()
+    }
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Stages only the selected changes of the working file, as `Stage Selected Ranges` of git.
+    ///
+    /// The hunks and ranges select the changes by their lines in the working file,
+    /// such as hunks returned by [`WasmTvcClient::diff_working`].
+    /// A patch is applied to the staged file, or the committed file if it isn't staged.
+    /// Returns the path collisions with the files staged or committed under the warn collision policy.
+    pub async fn stage_hunks(
+        &self,
+        branch_name: String,
+        path: String,
+        selection: JsValue,
+    ) -> error::Result<JsVecPathCollision> {
+        let selection = HunkSelection::from_js(selection)?;
+        let branch_name = BranchName(branch_name);
+        let path = normalize_path(&path);
+        let collisions = self.check_stage_collisions(&branch_name, &path).await?;
+        let Some(working) = self.fs.read_file(&path).await.into_js_result()? else {
+            return Err(TvcError::not_found("file", &path).into());
+        };
+        let working = into_text(&path, working)?;
+        let staged = self.read_staged_text(&branch_name, &path).await?.unwrap_or_default();
+
+        let text = match selection.patch.as_ref() {
+            Some(patch) => apply_patch(&staged, &parse_patch(patch)?).ok_or_else(|| patch_conflict(&path))?,
+            None => {
+                let selected = selection.selected_lines();
+                select_changes(&staged, &working, |block| is_selected(&selected, block))
+            }
+        };
+        self.write_staged_text(&branch_name, &path, text).await?;
+        self.emit(RepositoryEvent {
+            branch: Some(branch_name.0),
+            paths: vec![path],
+            ..RepositoryEvent::new(STAGED)
+        });
+        Ok(JsVecPathCollision(collisions))
+    }
+
+    /// Unstages only the selected changes of the staged file, the reverse of [`WasmTvcClient::stage_hunks`].
+    ///
+    /// The hunks and ranges select the changes by their lines in the staged file,
+    /// such as hunks returned by [`WasmTvcClient::diff_staged`],
+    /// and a patch is reverted from the staged file.
+    pub async fn un_stage_hunks(&self, branch_name: String, path: String, selection: JsValue) -> error::Result {
+        let selection = HunkSelection::from_js(selection)?;
+        let branch_name = BranchName(branch_name);
+        let path = normalize_path(&path);
+        let committed = self.read_committed_text(&branch_name, &path).await?.unwrap_or_default();
+        let Some(staged) = self.read_staged_text(&branch_name, &path).await? else {
+            return Err(TvcError::new(INVALID_OPERATION, format!("the file isn't staged: {path}")).into());
+        };
+
+        let text = match selection.patch.as_ref() {
+            Some(patch) => apply_patch(&staged, &parse_patch(patch)?.reverse()).ok_or_else(|| patch_conflict(&path))?,
+            None => {
+                let selected = selection.selected_lines();
+                select_changes(&committed, &staged, |block| !is_selected(&selected, block))
+            }
+        };
+        self.write_staged_text(&branch_name, &path, text).await?;
+        self.emit(RepositoryEvent {
+            branch: Some(branch_name.0),
+            paths: vec![path],
+            ..RepositoryEvent::new(UNSTAGED)
+        });
+        Ok(())
+    }
+}
+
+
+impl WasmTvcClient {
+    async fn read_committed_text(&self, branch_name: &BranchName, path: &str) -> error::Result<Option<String>> {
+        match self.head_tree(branch_name).await?.get(path) {
+            Some(blob) => Ok(Some(into_text(path, self.read_blob(blob).await?)?)),
+            None => Ok(None),
+        }
+    }
+
+
+    /// Reads the file in the staging area, or the committed file if it isn't staged.
+    ///
+    /// Returns `None` if the file is neither staged nor committed, or its deletion is staged.
+    async fn read_staged_text(&self, branch_name: &BranchName, path: &str) -> error::Result<Option<String>> {
+        match self.staged_tree(branch_name).await?.get(path) {
+            Some(blob) => Ok(Some(into_text(path, self.read_blob(blob).await?)?)),
+            None => Ok(None),
+        }
+    }
+
+
+    /// Writes `text` into the staging area as a file object,
+    /// or removes the file from it if `text` is the same as the committed file,
+    /// or is empty while the file isn't committed.
+    async fn write_staged_text(&self, branch_name: &BranchName, path: &str, text: String) -> error::Result {
+        let repository = self.repository();
+        let committed = self.read_committed_text(branch_name, path).await?;
+        if committed.map_or(text.is_empty(), |committed| committed == text) {
+            return repository.write_staged_obj(path, None).await;
+        }
+        let obj_hash = repository.write_file_obj(text.into_bytes()).await?;
+        repository.write_staged_obj(path, Some(obj_hash)).await
+    }
+}
+
+
+fn into_text(path: &str, buf: Vec<u8>) -> error::Result<String> {
+    String::from_utf8(buf)
+        .ok()
+        .filter(|text| !text.contains('\0'))
+        .ok_or_else(|| TvcError::new(INVALID_OPERATION, format!("can't stage hunks of a binary file: {path}")).into())
+}
+
+
+#[inline]
+fn patch_conflict(path: &str) -> JsValue {
+    TvcError::conflict(format!("patch does not apply: {path}"), [path]).into()
+}
+
+
+/// Whether a block of changes covering `block` of the new side is selected.
+///
+/// A block which only deletes lines is selected if a selection touches where they were.
+fn is_selected(selected: &[Range<usize>], block: &Range<usize>) -> bool {
+    selected.iter().any(|range| if block.is_empty() {
+        range.start <= block.start && block.start <= range.end
+    } else {
+        range.start < block.end && block.start < range.end
+    })
+}
+
+
+/// Builds the text which has the changes from `old` to `new` only in the blocks
+/// for which `selected` returns true, given the lines they cover on the new side.
+pub fn select_changes(old: &str, new: &str, selected: impl Fn(&Range<usize>) -> bool) -> String {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let new_lines: Vec<&str> = new.split_inclusive('\n').collect();
+    let mut text = String::new();
+    for op in similar::capture_diff_slices(Algorithm::Myers, &old_lines, &new_lines) {
+        let lines = if op.tag() != DiffTag::Equal && selected(&op.new_range()) {
+            &new_lines[op.new_range()]
+        } else {
+            &old_lines[op.old_range()]
+        };
+        for line in lines {
+            push_line(&mut text, line, line_ending(new));
+        }
+    }
+    text
+}
+
+
+/// Parses the hunks of a unified diff, skipping the file headers.
+pub fn parse_patch(patch: &str) -> error::Result<Patch> {
+    parse_hunks(patch).map_err(|message| TvcError::new(INVALID_OPERATION, message).into())
+}
+
+
+fn parse_hunks(patch: &str) -> std::result::Result<Patch, String> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    // The lines left on each side of the hunk being read.
+    let mut remaining = (0, 0);
+    for line in patch.lines() {
+        if line.starts_with('\\') {
+            // `\ No newline at end of file` follows the last line of the side it marks.
+            let Some(last) = hunks.last_mut().and_then(|hunk| hunk.lines.last_mut()) else {
+                return Err(format!("marker without a line: {line}"));
+            };
+            last.no_newline = true;
+            continue;
+        }
+        if remaining == (0, 0) {
+            if line.starts_with("@@") {
+                let hunk = parse_header(line).ok_or_else(|| format!("malformed hunk header: {line}"))?;
+                remaining = (hunk.old_lines, hunk.new_lines);
+                hunks.push(hunk);
+            }
+            continue;
+        }
+
+        let Some(hunk) = hunks.last_mut() else {
+            continue;
+        };
+        let (kind, content) = match line.split_at(line.len().min(1)) {
+            ("+", content) => (INSERT, content),
+            ("-", content) => (DELETE, content),
+            (" ", content) | ("", content) => (CONTEXT, content),
+            _ => return Err(format!("malformed hunk line: {line}")),
+        };
+        let old_line = (kind != INSERT).then(|| hunk.old_start + hunk.old_lines - remaining.0);
+        let new_line = (kind != DELETE).then(|| hunk.new_start + hunk.new_lines - remaining.1);
+        if old_line.is_some() {
+            remaining.0 = remaining.0.checked_sub(1).ok_or_else(|| format!("hunk is longer than its header: {line}"))?;
+        }
+        if new_line.is_some() {
+            remaining.1 = remaining.1.checked_sub(1).ok_or_else(|| format!("hunk is longer than its header: {line}"))?;
+        }
+        hunk.lines.push(DiffLine {
+            kind: kind.to_string(),
+            content: content.to_string(),
+            old_line,
+            new_line,
+            no_newline: false,
+        });
+    }
+
+    if remaining != (0, 0) {
+        Err("hunk is shorter than its header".to_string())
+    } else if hunks.is_empty() {
+        Err("no hunks in the patch".to_string())
+    } else {
+        Ok(Patch::new(hunks))
+    }
+}
+
+
+/// Parses a header such as `@@ -1,3 +1,4 @@`, where a count omitted is 1.
+fn parse_header(line: &str) -> Option<DiffHunk> {
+    let ranges = line.strip_prefix("@@ ")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(' ')?;
+    let (old_start, old_lines) = parse_range(old.strip_prefix('-')?)?;
+    let (new_start, new_lines) = parse_range(new.strip_prefix('+')?)?;
+    Some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines: Vec::new(),
+    })
+}
+
+
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    match range.split_once(',') {
+        Some((start, lines)) => Some((start.parse().ok()?, lines.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+
+/// Applies the patch to the text, or returns `None` if its deleted or context lines don't match it.
+///
+/// The text keeps whether it ends with a line ending unless the patch marks either side as missing it.
+pub fn apply_patch(text: &str, patch: &Patch) -> Option<String> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let eol = line_ending(text);
+    let text_missing_newline = !text.is_empty() && !text.ends_with('\n');
+    let missing_newline = match patch.missing_newline {
+        (false, false) => text_missing_newline,
+        (old, _) if old != text_missing_newline => return None,
+        (_, new) => new,
+    };
+    let mut patched = String::new();
+    let mut pos = 0;
+    for hunk in &patch.hunks {
+        // A hunk which only inserts lines points to the line just before them.
+        let start = if hunk.old_lines == 0 {
+            hunk.old_start as usize
+        } else {
+            (hunk.old_start as usize).checked_sub(1)?
+        };
+        if start < pos || lines.len() < start {
+            return None;
+        }
+        for line in &lines[pos..start] {
+            push_line(&mut patched, line, eol);
+        }
+        pos = start;
+
+        for line in &hunk.lines {
+            if line.kind == INSERT {
+                push_line(&mut patched, &line.content, eol);
+                patched.push_str(eol);
+                continue;
+            }
+            let original = lines.get(pos)?;
+            if strip_line_ending(original) != line.content {
+                return None;
+            }
+            if line.kind == CONTEXT {
+                push_line(&mut patched, original, eol);
+            }
+            pos += 1;
+        }
+    }
+    for line in &lines[pos..] {
+        push_line(&mut patched, line, eol);
+    }
+
+    if missing_newline {
+        if let Some(stripped) = patched.strip_suffix(eol) {
+            patched.truncate(stripped.len());
+        }
+    } else if !patched.is_empty() && !patched.ends_with('\n') {
+        patched.push_str(eol);
+    }
+    Some(patched)
+}
+
+
+/// Swaps the sides of the hunk, so that applying it reverts the original hunk.
+pub fn reverse_hunk(hunk: &DiffHunk) -> DiffHunk {
+    DiffHunk {
+        old_start: hunk.new_start,
+        old_lines: hunk.new_lines,
+        new_start: hunk.old_start,
+        new_lines: hunk.old_lines,
+        lines: hunk
+            .lines
+            .iter()
+            .map(|line| DiffLine {
+                kind: match line.kind.as_str() {
+                    INSERT => DELETE,
+                    DELETE => INSERT,
+                    _ => CONTEXT,
+                }.to_string(),
+                content: line.content.clone(),
+                old_line: line.new_line,
+                new_line: line.old_line,
+                no_newline: line.no_newline,
+            })
+            .collect(),
+    }
+}
+
+
+/// Appends a line, ending the previous line first if it was the last line without a line ending.
+fn push_line(text: &mut String, line: &str, eol: &str) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push_str(eol);
+    }
+    text.push_str(line);
+}
+
+
+#[inline]
+fn line_ending(text: &str) -> &'static str {
+    if text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use serde::Serialize;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, tvc_client, write_files};
+    use crate::tvc::diff::{diff_text, DiffHunk};
+    use crate::tvc::hunk::{apply_patch, HunkSelection, is_selected, parse_hunks, Patch, select_changes};
+    use crate::tvc::WasmTvcClient;
+
+    const OLD: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+    const NEW: &str = "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n";
+
+    fn selection(selection: &HunkSelection) -> JsValue {
+        selection.serialize(&serde_wasm_bindgen::Serializer::json_compatible()).unwrap()
+    }
+
+    fn hunks(hunks: Vec<DiffHunk>) -> JsValue {
+        selection(&HunkSelection {
+            hunks: Some(hunks),
+            ..HunkSelection::default()
+        })
+    }
+
+    async fn staged_text(client: &WasmTvcClient, path: &str) -> Option<String> {
+        client.read_staged_text(&BranchName("main".to_string()), path).await.unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    fn select_first_change() {
+        let text = select_changes(OLD, NEW, |block| is_selected(&[0..1], block));
+        assert_eq!(text, "one\n2\n3\n4\n5\n6\n7\n8\n9\n10\n");
+    }
+
+    #[wasm_bindgen_test]
+    fn select_deletion_at_its_position() {
+        let text = select_changes("a\nb\nc\nd\n", "a\nd\n", |block| is_selected(&[1..1], block));
+        assert_eq!(text, "a\nd\n");
+        let text = select_changes("a\nb\nc\nd\n", "a\nd\n", |block| is_selected(&[3..4], block));
+        assert_eq!(text, "a\nb\nc\nd\n");
+    }
+
+    #[wasm_bindgen_test]
+    fn apply_diff_hunk() {
+        let hunks = diff_text(OLD, NEW, 1, false);
+        assert_eq!(apply_patch(OLD, &Patch::new(hunks[1..].to_vec())).unwrap(), "1\n2\n3\n4\n5\n6\n7\n8\n9\nten\n");
+        assert_eq!(apply_patch(NEW, &Patch::new(hunks).reverse()).unwrap(), OLD);
+    }
+
+    #[wasm_bindgen_test]
+    fn apply_diff_hunk_adding_newline_at_end_of_file() {
+        let patch = Patch::new(diff_text("a\nb", "a\nb\n", 3, false));
+        assert_eq!(patch.missing_newline, (true, false));
+        assert_eq!(apply_patch("a\nb", &patch).unwrap(), "a\nb\n");
+        assert_eq!(apply_patch("a\nb\n", &patch.reverse()).unwrap(), "a\nb");
+    }
+
+    #[wasm_bindgen_test]
+    fn parse_unified_diff() {
+        let patch = "--- a/file\n+++ b/file\n@@ -1,2 +1,2 @@\n-1\n+one\n 2\n";
+        let patch = parse_hunks(patch).unwrap();
+        assert_eq!(patch.hunks.len(), 1);
+        assert_eq!(apply_patch(OLD, &patch).unwrap(), "one\n2\n3\n4\n5\n6\n7\n8\n9\n10\n");
+        assert_eq!(apply_patch("x\n2\n", &patch), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn add_and_remove_newline_at_end_of_file() {
+        let add = parse_hunks("@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n").unwrap();
+        assert_eq!(add.missing_newline, (true, false));
+        assert_eq!(apply_patch("a\nb", &add).unwrap(), "a\nb\n");
+        assert_eq!(apply_patch("a\nb\n", &add), None);
+        assert_eq!(apply_patch("a\nb\n", &add.reverse()).unwrap(), "a\nb");
+
+        let context = parse_hunks("@@ -1 +1,2 @@\n+a\n b\n\\ No newline at end of file\n").unwrap();
+        assert_eq!(context.missing_newline, (true, true));
+        assert_eq!(apply_patch("b", &context).unwrap(), "a\nb");
+    }
+
+    #[wasm_bindgen_test]
+    fn reject_hunk_starting_at_line_zero() {
+        let hunk = DiffHunk {
+            old_start: 0,
+            old_lines: 0,
+            new_start: 0,
+            new_lines: 1,
+            lines: Vec::new(),
+        };
+        assert!(HunkSelection::from_js(hunks(vec![hunk])).is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn stage_and_un_stage_hunks() {
+        let client = tvc_client("hunk_stage").await;
+        commit_files(&client, "main", &[("workspace/a.txt", OLD)]).await;
+        write_files(&client, &[("workspace/a.txt", NEW)]).await;
+
+        let diff = client.diff_working("main".to_string(), JsValue::UNDEFINED).await.unwrap();
+        let working_hunks = diff.0[0].hunks.clone();
+        assert_eq!(working_hunks.len(), 2);
+        client
+            .stage_hunks("main".to_string(), "workspace/a.txt".to_string(), hunks(working_hunks[1..].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(staged_text(&client, "workspace/a.txt").await.unwrap(), "1\n2\n3\n4\n5\n6\n7\n8\n9\nten\n");
+
+        client
+            .stage_hunks("main".to_string(), "workspace/a.txt".to_string(), hunks(working_hunks[..1].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(staged_text(&client, "workspace/a.txt").await.unwrap(), NEW);
+
+        let diff = client.diff_staged("main".to_string(), JsValue::UNDEFINED).await.unwrap();
+        let staged_hunks = diff.0[0].hunks.clone();
+        client
+            .un_stage_hunks("main".to_string(), "workspace/a.txt".to_string(), hunks(staged_hunks[..1].to_vec()))
+            .await
+            .unwrap();
+        assert_eq!(staged_text(&client, "workspace/a.txt").await.unwrap(), "1\n2\n3\n4\n5\n6\n7\n8\n9\nten\n");
+
+        client
+            .un_stage_hunks("main".to_string(), "workspace/a.txt".to_string(), hunks(staged_hunks[1..].to_vec()))
+            .await
+            .unwrap();
+        assert!(client.staging_files().await.unwrap().0.is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn stage_patch_adding_newline_at_end_of_file() {
+        let client = tvc_client("hunk_patch_newline").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "a\nb")]).await;
+        write_files(&client, &[("workspace/a.txt", "a\nb\nc\n")]).await;
+
+        let patch = "@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n";
+        let patch = selection(&HunkSelection {
+            patch: Some(patch.to_string()),
+            ..HunkSelection::default()
+        });
+        client.stage_hunks("main".to_string(), "workspace/a.txt".to_string(), patch.clone()).await.unwrap();
+        assert_eq!(staged_text(&client, "workspace/a.txt").await.unwrap(), "a\nb\n");
+
+        client.un_stage_hunks("main".to_string(), "workspace/a.txt".to_string(), patch).await.unwrap();
+        assert!(client.staging_files().await.unwrap().0.is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+use std::cmp::Reverse;
+use std::collections::{HashSet, VecDeque};
+
+use meltos_tvc::object::commit::{CommitHash, CommitObj};
+use meltos_tvc::object::ObjHash;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{INVALID_OPERATION, TvcError};
+use crate::tvc::repository::Repository;
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::worktree::is_under;
+use crate::tvc::WasmTvcClient;
+
+const DEFAULT_LIMIT: usize = 100;
+const ITEM_SEPARATOR: char = ',';
+const LIST_SEPARATOR: char = ';';
+/// The number of commits listed last which the cursor remembers.
+const SEEN_LIMIT: usize = 100;
+
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct LogOptions {
+    pub limit: Option<usize>,
+    /// The `next_cursor` returned by the previous page.
+    pub cursor: Option<String>,
+    /// Lists only the commits which changed this file or a file under this directory.
+    pub path: Option<String>,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub hash: String,
+    pub parents: Vec<String>,
+    pub message: String,
+    pub author: Option<String>,
+    pub co_authors: Vec<String>,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: Option<u64>,
+}
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LogPage {
+    pub entries: Vec<LogEntry>,
+    /// Pass this as `cursor` to read the next page, `undefined` if there are no more commits.
+    pub next_cursor: Option<String>,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Walks the history from `branch_or_commit` towards the initial commit, newest first.
+    ///
+    /// `options` is an object of `{ limit, cursor, path }`; all of them are optional.
+    pub async fn log(&self, branch_or_commit: String, options: JsValue) -> error::Result<LogPage> {
+        let options: LogOptions = if options.is_undefined() || options.is_null() {
+            LogOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(|e| TvcError::new(INVALID_OPERATION, e.to_string()))?
+        };
+        let repository = self.repository();
+        let (pending, seen) = match options.cursor.as_deref() {
+            Some(cursor) => decode_cursor(cursor),
+            None => (vec![repository.resolve(&branch_or_commit).await?], VecDeque::new()),
+        };
+        read_log(
+            &repository,
+            pending,
+            seen,
+            options.limit.unwrap_or(DEFAULT_LIMIT),
+            options.path.as_deref(),
+        ).await
+    }
+}
+
+
+/// A commit waiting to be listed.
+struct Pending {
+    commit_hash: CommitHash,
+    commit: CommitObj,
+    info: CommitInfo,
+}
+
+
+impl Pending {
+    async fn read(repository: &Repository, commit_hash: CommitHash) -> error::Result<Self> {
+        let commit = repository.read_commit(&commit_hash).await?;
+        let info = CommitInfo::parse(&commit.text.0);
+        Ok(Self {
+            commit_hash,
+            commit,
+            info,
+        })
+    }
+}
+
+
+/// Lists the commits reachable from `pending` by their timestamps, newest first.
+///
+/// A commit reachable through several paths, such as the base of a merge, is queued once,
+/// and is listed only after every newer commit, so it is usually queued from all of its paths before being listed.
+/// The commits listed last are passed as `seen` so that it isn't listed again
+/// if its children have equal or skewed timestamps; only the last [`SEEN_LIMIT`] of them are kept
+/// so that the cursor doesn't grow with the history.
+async fn read_log(
+    repository: &Repository,
+    pending: Vec<CommitHash>,
+    mut seen: VecDeque<CommitHash>,
+    limit: usize,
+    path: Option<&str>,
+) -> error::Result<LogPage> {
+    let mut visited: HashSet<CommitHash> = seen.iter().cloned().collect();
+    let mut frontier = Vec::with_capacity(pending.len());
+    for commit_hash in pending {
+        frontier.push(Pending::read(repository, commit_hash).await?);
+    }
+
+    let mut page = LogPage::default();
+    while let Some(newest) = newest(&frontier) {
+        if page.entries.len() == limit {
+            page.next_cursor = Some(encode_cursor(&frontier, &seen));
+            break;
+        }
+
+        let Pending {
+            commit_hash,
+            commit,
+            info,
+        } = frontier.remove(newest);
+        visited.insert(commit_hash.clone());
+        seen.push_back(commit_hash.clone());
+        if SEEN_LIMIT < seen.len() {
+            seen.pop_front();
+        }
+        for parent in &commit.parents {
+            if !visited.contains(parent) && !frontier.iter().any(|pending| &pending.commit_hash == parent) {
+                frontier.push(Pending::read(repository, parent.clone()).await?);
+            }
+        }
+        if let Some(path) = path {
+            let changed = repository.read_changed_paths(&commit).await?;
+            if !changed.iter().any(|changed| is_under(changed, path)) {
+                continue;
+            }
+        }
+
+        page.entries.push(LogEntry {
+            hash: commit_hash.0.0,
+            parents: commit.parents.iter().map(|parent| parent.0.0.clone()).collect(),
+            message: info.message,
+            author: info.author,
+            co_authors: info.co_authors,
+            timestamp: info.timestamp,
+        });
+    }
+    Ok(page)
+}
+
+
+/// Finds the commit with the latest timestamp, the one queued first among the same timestamps.
+///
+/// Commits without a timestamp, which were made before they were recorded, come last in the order queued.
+#[inline]
+fn newest(frontier: &[Pending]) -> Option<usize> {
+    frontier
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, pending)| (pending.info.timestamp.unwrap_or(0), Reverse(*i)))
+        .map(|(i, _)| i)
+}
+
+
+/// Encodes the commits waiting to be listed and the commits listed last as `pending;seen`.
+fn encode_cursor(frontier: &[Pending], seen: &VecDeque<CommitHash>) -> String {
+    let join = |hashes: Vec<&str>| hashes.join(&ITEM_SEPARATOR.to_string());
+    let pending = join(frontier.iter().map(|pending| pending.commit_hash.0.0.as_str()).collect());
+    let seen = join(seen.iter().map(|commit_hash| commit_hash.0.0.as_str()).collect());
+    format!("{pending}{LIST_SEPARATOR}{seen}")
+}
+
+
+fn decode_cursor(cursor: &str) -> (Vec<CommitHash>, VecDeque<CommitHash>) {
+    let split = |hashes: &str| {
+        hashes
+            .split(ITEM_SEPARATOR)
+            .filter(|hash| !hash.is_empty())
+            .map(|hash| CommitHash(ObjHash(hash.to_string())))
+            .collect::<Vec<_>>()
+    };
+    let (pending, seen) = cursor.split_once(LIST_SEPARATOR).unwrap_or((cursor, ""));
+    (split(pending), split(seen).into_iter().collect())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use meltos_tvc::object::commit::CommitHash;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::wasm_fs;
+    use crate::tvc::log::{decode_cursor, read_log, SEEN_LIMIT};
+    use crate::tvc::repository::{Repository, Snapshot};
+
+    async fn commit(repository: &Repository, parents: &[&CommitHash], message: &str, timestamp: u64) -> CommitHash {
+        repository
+            .write_detached_commit(
+                parents.iter().map(|parent| (*parent).clone()).collect(),
+                format!("{message}\n\nTimestamp: {timestamp}"),
+                &Snapshot::new(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn list_merge_base_once_across_pages() {
+        let repository = Repository::new(wasm_fs("log_diamond").await);
+        let root = commit(&repository, &[], "root", 1).await;
+        let left = commit(&repository, &[&root], "left", 3).await;
+        let right = commit(&repository, &[&root], "right", 2).await;
+        let merge = commit(&repository, &[&left, &right], "merge", 4).await;
+
+        let mut messages = Vec::new();
+        let (mut pending, mut seen) = (vec![merge], VecDeque::new());
+        loop {
+            let page = read_log(&repository, pending, seen, 1, None).await.unwrap();
+            messages.extend(page.entries.into_iter().map(|entry| entry.message));
+            let Some(cursor) = page.next_cursor else {
+                break;
+            };
+            (pending, seen) = decode_cursor(&cursor);
+        }
+        assert_eq!(messages, vec!["merge", "left", "right", "root"]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn list_newest_first() {
+        let repository = Repository::new(wasm_fs("log_newest_first").await);
+        let root = commit(&repository, &[], "root", 1).await;
+        let old = commit(&repository, &[&root], "old", 2).await;
+        let new = commit(&repository, &[&root], "new", 5).await;
+        let older = commit(&repository, &[&old], "older", 3).await;
+        let merge = commit(&repository, &[&older, &new], "merge", 6).await;
+
+        let page = read_log(&repository, vec![merge], VecDeque::new(), 10, None).await.unwrap();
+        let messages: Vec<String> = page.entries.into_iter().map(|entry| entry.message).collect();
+        assert_eq!(messages, vec!["merge", "new", "older", "old", "root"]);
+        assert_eq!(page.next_cursor, None);
+    }
+
+    #[wasm_bindgen_test]
+    async fn cursor_is_bounded() {
+        let repository = Repository::new(wasm_fs("log_bounded_cursor").await);
+        let mut head = commit(&repository, &[], "0", 0).await;
+        for i in 1..SEEN_LIMIT as u64 + 10 {
+            head = commit(&repository, &[&head], &i.to_string(), i).await;
+        }
+
+        let page = read_log(&repository, vec![head], VecDeque::new(), SEEN_LIMIT + 5, None).await.unwrap();
+        let (pending, seen) = decode_cursor(&page.next_cursor.unwrap());
+        assert_eq!(pending.len(), 1);
+        assert_eq!(seen.len(), SEEN_LIMIT);
+
+        let page = read_log(&repository, pending, seen, SEEN_LIMIT, None).await.unwrap();
+        let messages: Vec<String> = page.entries.into_iter().map(|entry| entry.message).collect();
+        assert_eq!(messages, vec!["4", "3", "2", "1", "0"]);
+        assert_eq!(page.next_cursor, None);
+    }
+}
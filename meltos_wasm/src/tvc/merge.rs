@@ -0,0 +1,723 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use meltos_tvc::object::commit::CommitHash;
+use meltos_tvc::object::ObjHash;
+use meltos_tvc::operation::merge::MergedStatus;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::abort::AbortSignal;
+use crate::error;
+use crate::error::{classify, IntoJsResult, INVALID_OPERATION, TvcError};
+use crate::progress::ProgressReporter;
+use crate::tvc::events;
+use crate::tvc::merge3::{conflict_markers, merge_text, MergeStrategy};
+use crate::tvc::repository::Snapshot;
+use crate::tvc::WasmTvcClient;
+
+pub const UP_TO_DATE: &str = "up-to-date";
+pub const FAST_FORWARD: &str = "fast-forward";
+pub const MERGED: &str = "merged";
+pub const CONFLICTED: &str = "conflicted";
+
+pub(crate) const MERGE_STATE: &str = "MERGE_STATE";
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MergeResult {
+    /// One of `up-to-date`, `fast-forward`, `merged`, `rebased` or `conflicted`.
+    pub kind: String,
+    /// The head of the branch after merging, `undefined` while conflicted.
+    pub commit: Option<String>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+
+/// A file changed on both sides which couldn't be merged.
+///
+/// Each hash is `undefined` if the file doesn't exist on that side.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub path: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+
+/// A merge paused by conflicts, saved until it is concluded or aborted.
+///
+/// A rebase paused by conflicts saves one as well, so that they are resolved in the same way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct MergeState {
+    pub branch: String,
+    pub parents: Vec<String>,
+    pub message: String,
+    /// The author of the commit if it isn't the current user, as in a cherry-pick.
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The merged files except the conflicted ones.
+    pub merged: BTreeMap<String, String>,
+    pub conflicts: Vec<MergeConflict>,
+    pub resolved: BTreeSet<String>,
+}
+
+
+/// The outcome of merging three snapshots.
+pub(crate) struct MergedTree {
+    pub snapshot: Snapshot,
+    /// The conflicts with the content written into the working file, `None` to leave it deleted.
+    pub conflicts: Vec<(MergeConflict, Option<Vec<u8>>)>,
+    /// Whether a file changed on both sides was merged line by line or picked by the strategy.
+    pub merged_files: bool,
+}
+
+
+enum FileMerge {
+    /// Merged without conflicts, `None` if the file is deleted.
+    Clean(Option<ObjHash>),
+    /// Conflicted with the content written into the working file, `None` to leave it deleted.
+    Conflict(Option<Vec<u8>>),
+}
+
+
+impl MergeState {
+    pub fn unresolved(&self) -> impl Iterator<Item=&str> {
+        self
+            .conflicts
+            .iter()
+            .filter(|conflict| !self.resolved.contains(&conflict.path))
+            .map(|conflict| conflict.path.as_str())
+    }
+}
+
+
+impl MergeResult {
+    #[inline]
+    pub fn new(kind: &str, commit: Option<&CommitHash>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            commit: commit.map(|commit| commit.0.0.clone()),
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Merges `source_commit_hash`, a branch name, a tag name or a commit hash,
+    /// into the branch with the common ancestor as the base.
+    ///
+    /// A fast-forward, and a merge in which no file is changed on both sides,
+    /// is done by tvc itself and its result is returned.
+    /// Text files changed on both sides are merged line by line, and `strategy` selects
+    /// how hunks changed on both sides are merged; conflict markers by default.
+    /// Binary files are never written with conflict markers; ours is left in the working file.
+    /// If the branch is checked out, the working files are updated, reporting each to `progress`,
+    /// and conflicted files are written with conflict markers.
+    /// A conflicted merge is concluded by [`WasmTvcClient::conclude_merge`]
+    /// after every conflict is marked resolved, or cancelled by [`WasmTvcClient::abort_merge`].
+    pub async fn merge(
+        &self,
+        branch_name: String,
+        source_commit_hash: String,
+        strategy: Option<MergeStrategy>,
+        progress: Option<ProgressReporter>,
+        signal: Option<AbortSignal>,
+    ) -> error::Result<MergeResult> {
+        self.observe_heads(None, self.with_progress(progress).abortable(signal, |client| async move {
+            let branch_name = BranchName(branch_name);
+            let source = client.repository().resolve(&source_commit_hash).await?;
+            let message = format!("merge {source_commit_hash} into {branch_name}");
+            let result = client.merge_commit(&branch_name, &source, message, strategy.unwrap_or_default()).await?;
+            client.emit_merge_result(events::MERGED, &branch_name, &result);
+            Ok(result)
+        })).await
+    }
+
+    /// Marks the conflicted file resolved with the current content of its working file.
+    pub async fn resolve_conflict(&self, path: String) -> error::Result {
+        let mut state = self.read_merge_state().await?;
+        if !state.conflicts.iter().any(|conflict| conflict.path == path) {
+            return Err(TvcError::new(INVALID_OPERATION, format!("not conflicted: {path}")).into());
+        }
+        state.resolved.insert(path);
+        self.repository().write_state(MERGE_STATE, &state).await
+    }
+
+    /// Commits the merge paused by conflicts.
+    pub async fn conclude_merge(&self, message: Option<String>) -> error::Result<MergeResult> {
+        self.observe_heads(None, async {
+            self.ensure_not_rebasing().await?;
+            let (state, snapshot) = self.read_resolved_merge().await?;
+            let repository = self.repository();
+            let parents = state
+                .parents
+                .iter()
+                .map(|parent| CommitHash(ObjHash(parent.clone())))
+                .collect();
+            let message = self.sign_as(&message.unwrap_or(state.message), state.author, Vec::new());
+            let branch_name = BranchName(state.branch);
+            let commit_hash = repository
+                .write_commit(&branch_name, parents, message, &snapshot)
+                .await?;
+            repository.delete_state(MERGE_STATE).await?;
+            self.emit_commit(events::MERGED, &branch_name, &commit_hash);
+            Ok(MergeResult::new(MERGED, Some(&commit_hash)))
+        }).await
+    }
+
+    /// Cancels the merge paused by conflicts and restores the working files.
+    pub async fn abort_merge(&self) -> error::Result {
+        self.ensure_not_rebasing().await?;
+        let state = self.read_merge_state().await?;
+        let repository = self.repository();
+        let head = CommitHash(ObjHash(state.parents[0].clone()));
+        let ours = repository.read_snapshot(&head).await?;
+
+        let conflicted: BTreeSet<&String> = state.conflicts.iter().map(|conflict| &conflict.path).collect();
+        let changed = state
+            .merged
+            .iter()
+            .filter(|(path, obj_hash)| ours.get(*path).map(|hash| &hash.0) != Some(*obj_hash))
+            .map(|(path, _)| path);
+        let deleted = ours
+            .keys()
+            .filter(|path| !state.merged.contains_key(*path) && !conflicted.contains(path));
+        let touched: BTreeSet<&String> = changed.chain(deleted).chain(conflicted.iter().copied()).collect();
+
+        for path in touched {
+            match ours.get(path) {
+                Some(obj_hash) => {
+                    let buf = repository.read_file_obj(obj_hash).await?;
+                    self.fs.write_file(path, &buf).await.into_js_result()?;
+                }
+                None => self.fs.delete(path).await.into_js_result()?,
+            }
+        }
+        repository.delete_state(MERGE_STATE).await
+    }
+
+    pub async fn is_merging(&self) -> error::Result<bool> {
+        Ok(self.repository().read_state::<MergeState>(MERGE_STATE).await?.is_some())
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Merges `source` into the branch, creating a merge commit with `message` if necessary.
+    pub(crate) async fn merge_commit(
+        &self,
+        branch_name: &BranchName,
+        source: &CommitHash,
+        message: String,
+        strategy: MergeStrategy,
+    ) -> error::Result<MergeResult> {
+        let repository = self.repository();
+        self.ensure_not_merging().await?;
+        let Some(head) = repository.read_head(branch_name).await? else {
+            return Err(TvcError::not_found("branch", &branch_name).into());
+        };
+        let base = repository.merge_base(&head, source).await?;
+        if base.as_ref() == Some(source) {
+            return Ok(MergeResult::new(UP_TO_DATE, Some(&head)));
+        }
+
+        let checked_out = self.is_checked_out(branch_name).await?;
+        if checked_out {
+            self.ensure_no_changes(branch_name).await?;
+        }
+        let ours = repository.read_snapshot(&head).await?;
+        let theirs = repository.read_snapshot(source).await?;
+
+        if base.as_ref() != Some(&head) {
+            let base_snapshot = match base.as_ref() {
+                Some(base) => repository.read_snapshot(base).await?,
+                None => Snapshot::new(),
+            };
+            let merged = self.merge_snapshots(&base_snapshot, &ours, &theirs, strategy).await?;
+            if merged.merged_files || !merged.conflicts.is_empty() {
+                let parents = vec![head, source.clone()];
+                return self.apply_merged_tree(branch_name, parents, message, None, &ours, merged).await;
+            }
+        }
+
+        let status = self
+            .tvc
+            .merge(branch_name.clone(), source.clone())
+            .await
+            .map_err(classify)?;
+        let Some(merged_head) = repository.read_head(branch_name).await? else {
+            return Err(TvcError::not_found("branch", &branch_name).into());
+        };
+        if checked_out {
+            self.write_working_files(&ours, &repository.read_snapshot(&merged_head).await?).await?;
+        }
+        Ok(MergeResult::new(merge_kind(&status), Some(&merged_head)))
+    }
+
+
+    /// Commits the merged tree made by `author`, or the current user if `None`,
+    /// or writes the conflicts into the working files and saves the merge state if there are conflicts.
+    pub(crate) async fn apply_merged_tree(
+        &self,
+        branch_name: &BranchName,
+        parents: Vec<CommitHash>,
+        message: String,
+        author: Option<String>,
+        ours: &Snapshot,
+        merged: MergedTree,
+    ) -> error::Result<MergeResult> {
+        let repository = self.repository();
+        let checked_out = self.is_checked_out(branch_name).await?;
+        if merged.conflicts.is_empty() {
+            let message = self.sign_as(&message, author, Vec::new());
+            let commit_hash = repository.write_commit(branch_name, parents, message, &merged.snapshot).await?;
+            if checked_out {
+                self.write_working_files(ours, &merged.snapshot).await?;
+            }
+            return Ok(MergeResult::new(MERGED, Some(&commit_hash)));
+        }
+
+        if !checked_out {
+            return Err(TvcError::new(INVALID_OPERATION, format!(
+                "conflicts can be resolved only on the checked out branch: {branch_name}"
+            )).into());
+        }
+        let conflicts = self.write_merged_working_files(ours, &merged).await?;
+        self.pause_merge(branch_name, parents, message, author, merged.snapshot, conflicts.clone()).await?;
+        Ok(MergeResult {
+            kind: CONFLICTED.to_string(),
+            commit: None,
+            conflicts,
+        })
+    }
+
+
+    /// Rewrites the working files committed in `ours` into the merged files,
+    /// writing the conflicted files with their conflict markers.
+    pub(crate) async fn write_merged_working_files(
+        &self,
+        ours: &Snapshot,
+        merged: &MergedTree,
+    ) -> error::Result<Vec<MergeConflict>> {
+        let conflicted: BTreeSet<&String> = merged.conflicts.iter().map(|(conflict, _)| &conflict.path).collect();
+        let mut clean_ours = ours.clone();
+        clean_ours.retain(|path, _| !conflicted.contains(path));
+        self.write_working_files(&clean_ours, &merged.snapshot).await?;
+        for (conflict, buf) in &merged.conflicts {
+            if let Some(buf) = buf {
+                self.fs.write_file(&conflict.path, buf).await.into_js_result()?;
+            }
+        }
+        Ok(merged.conflicts.iter().map(|(conflict, _)| conflict.clone()).collect())
+    }
+
+
+    /// Merges the changes from `base` to `ours` and from `base` to `theirs`.
+    ///
+    /// Text files changed on both sides are merged line by line,
+    /// and the hunks changed on both sides are merged by `strategy`.
+    pub(crate) async fn merge_snapshots(
+        &self,
+        base: &Snapshot,
+        ours: &Snapshot,
+        theirs: &Snapshot,
+        strategy: MergeStrategy,
+    ) -> error::Result<MergedTree> {
+        let paths: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+        let mut merged = MergedTree {
+            snapshot: Snapshot::new(),
+            conflicts: Vec::new(),
+            merged_files: false,
+        };
+        for path in paths {
+            let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+            let resolved = if o == t || t == b {
+                o.cloned()
+            } else if o == b {
+                t.cloned()
+            } else {
+                merged.merged_files = true;
+                match self.merge_file(b, o, t, strategy).await? {
+                    FileMerge::Clean(obj_hash) => obj_hash,
+                    FileMerge::Conflict(buf) => {
+                        merged.conflicts.push((MergeConflict {
+                            path: path.clone(),
+                            base: b.map(|hash| hash.0.clone()),
+                            ours: o.map(|hash| hash.0.clone()),
+                            theirs: t.map(|hash| hash.0.clone()),
+                        }, buf));
+                        continue;
+                    }
+                }
+            };
+            if let Some(obj_hash) = resolved {
+                merged.snapshot.insert(path.clone(), obj_hash);
+            }
+        }
+        Ok(merged)
+    }
+
+
+    /// Merges a file changed on both sides.
+    ///
+    /// A file deleted on one side or a binary file can't be merged line by line,
+    /// so it is a conflict unless `strategy` picks one side.
+    async fn merge_file(
+        &self,
+        base: Option<&ObjHash>,
+        ours: Option<&ObjHash>,
+        theirs: Option<&ObjHash>,
+        strategy: MergeStrategy,
+    ) -> error::Result<FileMerge> {
+        if let (Some(ours), Some(theirs)) = (ours, theirs) {
+            let repository = self.repository();
+            let base_buf = match base {
+                Some(base) => repository.read_file_obj(base).await?,
+                None => Vec::new(),
+            };
+            let ours_buf = repository.read_file_obj(ours).await?;
+            let theirs_buf = repository.read_file_obj(theirs).await?;
+            if let (Ok(base_text), Ok(ours_text), Ok(theirs_text)) = (
+                String::from_utf8(base_buf),
+                String::from_utf8(ours_buf),
+                String::from_utf8(theirs_buf),
+            ) {
+                let merged = merge_text(&base_text, &ours_text, &theirs_text, strategy);
+                return if merged.conflicts == 0 {
+                    Ok(FileMerge::Clean(Some(repository.write_file_obj(merged.text.into_bytes()).await?)))
+                } else {
+                    Ok(FileMerge::Conflict(Some(merged.text.into_bytes())))
+                };
+            }
+        }
+
+        match strategy {
+            MergeStrategy::Ours => Ok(FileMerge::Clean(ours.cloned())),
+            MergeStrategy::Theirs => Ok(FileMerge::Clean(theirs.cloned())),
+            MergeStrategy::Normal | MergeStrategy::Union => {
+                let ours_buf = self.read_optional_obj(ours).await?;
+                let theirs_buf = self.read_optional_obj(theirs).await?;
+                if is_binary(&ours_buf) || is_binary(&theirs_buf) {
+                    // Conflict markers would break a binary file, so leave ours in the working file.
+                    let ours_buf = match ours {
+                        Some(ours) => Some(self.repository().read_file_obj(ours).await?),
+                        None => None,
+                    };
+                    return Ok(FileMerge::Conflict(ours_buf));
+                }
+                let base_buf = self.read_optional_obj(base).await?;
+                let markers = conflict_markers(
+                    &String::from_utf8_lossy(&base_buf),
+                    &String::from_utf8_lossy(&ours_buf),
+                    &String::from_utf8_lossy(&theirs_buf),
+                );
+                Ok(FileMerge::Conflict(Some(markers.into_bytes())))
+            }
+        }
+    }
+
+
+    async fn read_optional_obj(&self, obj_hash: Option<&ObjHash>) -> error::Result<Vec<u8>> {
+        let Some(obj_hash) = obj_hash else {
+            return Ok(Vec::new());
+        };
+        let mut buf = self.repository().read_file_obj(obj_hash).await?;
+        if !buf.is_empty() && !buf.ends_with(b"\n") {
+            buf.push(b'\n');
+        }
+        Ok(buf)
+    }
+
+
+    pub(crate) async fn is_checked_out(&self, branch_name: &BranchName) -> error::Result<bool> {
+        Ok(self.repository().read_working_branch().await?.as_ref() == Some(branch_name))
+    }
+
+
+    pub(crate) async fn ensure_not_merging(&self) -> error::Result {
+        if self.is_merging().await? {
+            Err(TvcError::new(INVALID_OPERATION, "a merge is in progress; conclude or abort it first").into())
+        } else {
+            Ok(())
+        }
+    }
+
+
+    /// Reads the paused merge and its files, the conflicted ones taken from the working files.
+    ///
+    /// Fails if any conflict isn't marked resolved.
+    pub(crate) async fn read_resolved_merge(&self) -> error::Result<(MergeState, Snapshot)> {
+        let state = self.read_merge_state().await?;
+        let unresolved: Vec<&str> = state.unresolved().collect();
+        if !unresolved.is_empty() {
+            return Err(TvcError::conflict(
+                format!("unresolved conflicts: {}", unresolved.join(", ")),
+                unresolved,
+            ).into());
+        }
+
+        let repository = self.repository();
+        let mut snapshot: Snapshot = state
+            .merged
+            .iter()
+            .map(|(path, obj_hash)| (path.clone(), ObjHash(obj_hash.clone())))
+            .collect();
+        for conflict in &state.conflicts {
+            match self.fs.read_file(&conflict.path).await.into_js_result()? {
+                Some(buf) => snapshot.insert(conflict.path.clone(), repository.write_file_obj(buf).await?),
+                None => snapshot.remove(&conflict.path),
+            };
+        }
+        Ok((state, snapshot))
+    }
+
+
+    /// Saves the merge paused by `conflicts` until they are resolved.
+    pub(crate) async fn pause_merge(
+        &self,
+        branch_name: &BranchName,
+        parents: Vec<CommitHash>,
+        message: String,
+        author: Option<String>,
+        merged: Snapshot,
+        conflicts: Vec<MergeConflict>,
+    ) -> error::Result {
+        self.repository().write_state(MERGE_STATE, &MergeState {
+            branch: branch_name.0.clone(),
+            parents: parents.into_iter().map(|parent| parent.0.0).collect(),
+            message,
+            author,
+            merged: merged
+                .into_iter()
+                .map(|(path, obj_hash)| (path, obj_hash.0))
+                .collect(),
+            conflicts,
+            resolved: BTreeSet::new(),
+        }).await
+    }
+
+
+    /// The conflicted files not yet marked resolved, empty if no merge is in progress.
+    pub(crate) async fn unresolved_conflicts(&self) -> error::Result<BTreeSet<String>> {
+        let state: Option<MergeState> = self.repository().read_state(MERGE_STATE).await?;
+        Ok(state
+            .map(|state| state.unresolved().map(str::to_string).collect())
+            .unwrap_or_default())
+    }
+
+
+    async fn read_merge_state(&self) -> error::Result<MergeState> {
+        self
+            .repository()
+            .read_state(MERGE_STATE)
+            .await?
+            .ok_or_else(|| TvcError::new(INVALID_OPERATION, "no merge is in progress").into())
+    }
+}
+
+
+/// The kind of the merge done by tvc; it is never up to date since that is checked beforehand.
+fn merge_kind(status: &MergedStatus) -> &'static str {
+    match status {
+        MergedStatus::Merged => MERGED,
+        _ => FAST_FORWARD,
+    }
+}
+
+
+#[inline]
+fn is_binary(buf: &[u8]) -> bool {
+    std::str::from_utf8(buf).is_err()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, diverged_client, tvc_client, write_files};
+    use crate::tvc::merge::{CONFLICTED, FAST_FORWARD, MERGED, UP_TO_DATE};
+    use crate::tvc::merge3::MergeStrategy;
+    use crate::tvc::WasmTvcClient;
+
+    async fn read(client: &WasmTvcClient, path: &str) -> Option<Vec<u8>> {
+        client.fs.read_file(path).await.unwrap()
+    }
+
+    async fn merge(client: &WasmTvcClient, strategy: Option<MergeStrategy>) -> crate::tvc::merge::MergeResult {
+        client
+            .merge("main".to_string(), "feature".to_string(), strategy, None, None)
+            .await
+            .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn up_to_date() {
+        let client = tvc_client("merge_up_to_date").await;
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+
+        let result = merge(&client, None).await;
+        assert_eq!(result.kind, UP_TO_DATE);
+        assert_eq!(result.commit, Some(head.0.0));
+    }
+
+    #[wasm_bindgen_test]
+    async fn fast_forward_adds_no_local_commits() {
+        let client = tvc_client("merge_fast_forward").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        let feature_head = commit_files(&client, "feature", &[("workspace/a.txt", "b")]).await;
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        let main = BranchName("main".to_string());
+        let local_commits = client.repository().read_local_commits(&main).await.unwrap();
+
+        let result = merge(&client, None).await;
+        assert_eq!(result.kind, FAST_FORWARD);
+        assert_eq!(result.commit, Some(feature_head.0.0.clone()));
+        assert_eq!(client.repository().read_head(&main).await.unwrap(), Some(feature_head));
+        assert_eq!(client.repository().read_local_commits(&main).await.unwrap(), local_commits);
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"b".to_vec()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn merge_lines_changed_on_each_side() {
+        let client = diverged_client(
+            "merge_lines",
+            &[("workspace/a.txt", "1\n2\n3\n4\n5\n")],
+            &[("workspace/a.txt", "one\n2\n3\n4\n5\n")],
+            &[("workspace/a.txt", "1\n2\n3\n4\nfive\n")],
+        ).await;
+        let repository = client.repository();
+        let main = BranchName("main".to_string());
+        let ours = repository.read_head(&main).await.unwrap().unwrap();
+        let theirs = repository.read_head(&BranchName("feature".to_string())).await.unwrap().unwrap();
+
+        let result = merge(&client, None).await;
+        assert_eq!(result.kind, MERGED);
+        let head = repository.read_head(&main).await.unwrap().unwrap();
+        assert_eq!(result.commit, Some(head.0.0.clone()));
+        assert_eq!(repository.read_commit(&head).await.unwrap().parents, vec![ours, theirs]);
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"one\n2\n3\n4\nfive\n".to_vec()));
+        assert!(!client.is_merging().await.unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    async fn conclude_after_resolving_conflicts() {
+        let client = diverged_client(
+            "merge_conclude",
+            &[("workspace/a.txt", "base\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n")],
+        ).await;
+
+        let result = merge(&client, None).await;
+        assert_eq!(result.kind, CONFLICTED);
+        assert_eq!(result.commit, None);
+        let conflicted: Vec<&str> = result.conflicts.iter().map(|conflict| conflict.path.as_str()).collect();
+        assert_eq!(conflicted, vec!["workspace/a.txt"]);
+        assert_eq!(
+            read(&client, "workspace/a.txt").await,
+            Some(b"<<<<<<< ours\nours\n||||||| base\nbase\n=======\ntheirs\n>>>>>>> theirs\n".to_vec())
+        );
+        assert!(client.is_merging().await.unwrap());
+        assert!(client.conclude_merge(None).await.is_err());
+
+        write_files(&client, &[("workspace/a.txt", "resolved\n")]).await;
+        client.resolve_conflict("workspace/a.txt".to_string()).await.unwrap();
+        let result = client.conclude_merge(None).await.unwrap();
+        assert_eq!(result.kind, MERGED);
+
+        let repository = client.repository();
+        let head = repository.read_head(&BranchName("main".to_string())).await.unwrap().unwrap();
+        assert_eq!(result.commit, Some(head.0.0.clone()));
+        assert_eq!(repository.read_commit(&head).await.unwrap().parents.len(), 2);
+        let snapshot = repository.read_snapshot(&head).await.unwrap();
+        let resolved = repository.read_file_obj(&snapshot["workspace/a.txt"]).await.unwrap();
+        assert_eq!(resolved, b"resolved\n".to_vec());
+        assert!(!client.is_merging().await.unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    async fn abort_restores_working_files() {
+        let client = diverged_client(
+            "merge_abort",
+            &[("workspace/a.txt", "base\n"), ("workspace/b.txt", "b\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n"), ("workspace/c.txt", "c\n")],
+        ).await;
+        let main = BranchName("main".to_string());
+        let head = client.repository().read_head(&main).await.unwrap();
+
+        assert_eq!(merge(&client, None).await.kind, CONFLICTED);
+        assert_eq!(read(&client, "workspace/c.txt").await, Some(b"c\n".to_vec()));
+
+        client.abort_merge().await.unwrap();
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"ours\n".to_vec()));
+        assert_eq!(read(&client, "workspace/b.txt").await, Some(b"b\n".to_vec()));
+        assert_eq!(read(&client, "workspace/c.txt").await, None);
+        assert_eq!(client.repository().read_head(&main).await.unwrap(), head);
+        assert!(!client.is_merging().await.unwrap());
+        assert!(client.abort_merge().await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn conflict_markers_with_base_for_deleted_file() {
+        let client = tvc_client("merge_deleted").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "base\n")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        client.fs.delete("workspace/a.txt").await.unwrap();
+        commit_files(&client, "feature", &[("workspace/b.txt", "b\n")]).await;
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        commit_files(&client, "main", &[("workspace/a.txt", "ours\n")]).await;
+
+        assert_eq!(merge(&client, None).await.kind, CONFLICTED);
+        assert_eq!(
+            read(&client, "workspace/a.txt").await,
+            Some(b"<<<<<<< ours\nours\n||||||| base\nbase\n=======\n>>>>>>> theirs\n".to_vec())
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn no_conflict_markers_in_binary_files() {
+        let client = tvc_client("merge_binary").await;
+        client.fs.write_file("workspace/a.bin", &[0xff, 0x00]).await.unwrap();
+        commit_files(&client, "main", &[]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        client.checkout("feature".to_string(), None, None).await.unwrap();
+        client.fs.delete("workspace/a.bin").await.unwrap();
+        commit_files(&client, "feature", &[("workspace/b.txt", "b")]).await;
+        client.checkout("main".to_string(), None, None).await.unwrap();
+        client.fs.write_file("workspace/a.bin", &[0xff, 0x01]).await.unwrap();
+        commit_files(&client, "main", &[]).await;
+
+        let result = merge(&client, None).await;
+        assert_eq!(result.kind, CONFLICTED);
+        assert_eq!(read(&client, "workspace/a.bin").await, Some(vec![0xff, 0x01]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn strategy_picks_side_of_conflicts() {
+        let client = diverged_client(
+            "merge_theirs",
+            &[("workspace/a.txt", "base\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n")],
+        ).await;
+
+        let result = merge(&client, Some(MergeStrategy::Theirs)).await;
+        assert_eq!(result.kind, MERGED);
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"theirs\n".to_vec()));
+    }
+}
@@ -0,0 +1,227 @@
+use std::ops::Range;
+
+use similar::{Algorithm, DiffTag};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const OURS_MARKER: &str = "<<<<<<< ours\n";
+const BASE_MARKER: &str = "||||||| base\n";
+const SEPARATOR: &str = "=======\n";
+const THEIRS_MARKER: &str = ">>>>>>> theirs\n";
+
+
+/// How hunks changed on both sides are merged.
+#[wasm_bindgen]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum MergeStrategy {
+    /// Writes diff3-style conflict markers.
+    #[default]
+    Normal,
+    Ours,
+    Theirs,
+    /// Takes the lines of both sides, ours first.
+    Union,
+}
+
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TextMerge {
+    pub text: String,
+    /// The number of hunks written with conflict markers.
+    pub conflicts: usize,
+}
+
+
+/// A run of base lines replaced by a run of lines on one side.
+#[derive(Debug)]
+struct Change {
+    base: Range<usize>,
+    side: Range<usize>,
+}
+
+
+/// Merges the changes from `base` to `ours` and from `base` to `theirs` line by line.
+///
+/// Hunks changed on only one side are applied as they are,
+/// and hunks changed on both sides are merged by `strategy` unless both sides are the same.
+pub fn merge_text(base: &str, ours: &str, theirs: &str, strategy: MergeStrategy) -> TextMerge {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs.split_inclusive('\n').collect();
+    let ours_changes = changes(&base_lines, &ours_lines);
+    let theirs_changes = changes(&base_lines, &theirs_lines);
+
+    let mut merged = TextMerge {
+        text: String::new(),
+        conflicts: 0,
+    };
+    let (mut o, mut t) = (0, 0);
+    let mut pos = 0;
+    while o < ours_changes.len() || t < theirs_changes.len() {
+        let start = match (ours_changes.get(o), theirs_changes.get(t)) {
+            (Some(ours), Some(theirs)) => ours.base.start.min(theirs.base.start),
+            (Some(ours), None) => ours.base.start,
+            (None, Some(theirs)) => theirs.base.start,
+            (None, None) => unreachable!(),
+        };
+        merged.text.extend(base_lines[pos..start].iter().copied());
+
+        // Grow the region while a change of either side overlaps or touches it.
+        let (ours_from, theirs_from) = (o, t);
+        let mut end = start;
+        loop {
+            if let Some(change) = ours_changes.get(o).filter(|change| change.base.start <= end) {
+                end = end.max(change.base.end);
+                o += 1;
+            } else if let Some(change) = theirs_changes.get(t).filter(|change| change.base.start <= end) {
+                end = end.max(change.base.end);
+                t += 1;
+            } else {
+                break;
+            }
+        }
+
+        let region = start..end;
+        let ours_region = apply(&base_lines, &ours_lines, &region, &ours_changes[ours_from..o]);
+        let theirs_region = apply(&base_lines, &theirs_lines, &region, &theirs_changes[theirs_from..t]);
+        if ours_from == o {
+            merged.text.push_str(&theirs_region);
+        } else if theirs_from == t || ours_region == theirs_region {
+            merged.text.push_str(&ours_region);
+        } else {
+            let base_region: String = base_lines[region].concat();
+            resolve_conflict(&mut merged, &base_region, &ours_region, &theirs_region, strategy);
+        }
+        pos = end;
+    }
+    merged.text.extend(base_lines[pos..].iter().copied());
+    merged
+}
+
+
+/// Writes the whole of both sides with diff3-style conflict markers,
+/// for files which can't be merged line by line such as a file deleted on one side.
+pub fn conflict_markers(base: &str, ours: &str, theirs: &str) -> String {
+    let mut merged = TextMerge {
+        text: String::new(),
+        conflicts: 0,
+    };
+    resolve_conflict(&mut merged, base, ours, theirs, MergeStrategy::Normal);
+    merged.text
+}
+
+
+fn changes(base: &[&str], side: &[&str]) -> Vec<Change> {
+    let mut changes: Vec<Change> = Vec::new();
+    for op in similar::capture_diff_slices(Algorithm::Myers, base, side) {
+        if op.tag() == DiffTag::Equal {
+            continue;
+        }
+        match changes.last_mut() {
+            Some(last) if last.base.end == op.old_range().start => {
+                last.base.end = op.old_range().end;
+                last.side.end = op.new_range().end;
+            }
+            _ => changes.push(Change {
+                base: op.old_range(),
+                side: op.new_range(),
+            }),
+        }
+    }
+    changes
+}
+
+
+fn apply(base: &[&str], side: &[&str], region: &Range<usize>, changes: &[Change]) -> String {
+    let mut text = String::new();
+    let mut pos = region.start;
+    for change in changes {
+        text.extend(base[pos..change.base.start].iter().copied());
+        text.extend(side[change.side.clone()].iter().copied());
+        pos = change.base.end;
+    }
+    text.extend(base[pos..region.end].iter().copied());
+    text
+}
+
+
+fn resolve_conflict(merged: &mut TextMerge, base: &str, ours: &str, theirs: &str, strategy: MergeStrategy) {
+    match strategy {
+        MergeStrategy::Ours => merged.text.push_str(ours),
+        MergeStrategy::Theirs => merged.text.push_str(theirs),
+        MergeStrategy::Union => {
+            push_lines(&mut merged.text, ours);
+            merged.text.push_str(theirs);
+        }
+        MergeStrategy::Normal => {
+            merged.text.push_str(OURS_MARKER);
+            push_lines(&mut merged.text, ours);
+            merged.text.push_str(BASE_MARKER);
+            push_lines(&mut merged.text, base);
+            merged.text.push_str(SEPARATOR);
+            push_lines(&mut merged.text, theirs);
+            merged.text.push_str(THEIRS_MARKER);
+            merged.conflicts += 1;
+        }
+    }
+}
+
+
+/// Pushes `lines` terminating the last line so that the following line starts on its own.
+#[inline]
+fn push_lines(text: &mut String, lines: &str) {
+    text.push_str(lines);
+    if !lines.is_empty() && !lines.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tvc::merge3::{merge_text, MergeStrategy};
+
+    const BASE: &str = "1\n2\n3\n4\n5\n";
+
+    #[wasm_bindgen_test]
+    fn apply_changes_of_both_sides() {
+        let merged = merge_text(BASE, "one\n2\n3\n4\n5\n", "1\n2\n3\n4\nfive\n", MergeStrategy::Normal);
+        assert_eq!(merged.text, "one\n2\n3\n4\nfive\n");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn same_change_on_both_sides() {
+        let merged = merge_text(BASE, "1\ntwo\n3\n4\n5\n", "1\ntwo\n3\n4\n5\n", MergeStrategy::Normal);
+        assert_eq!(merged.text, "1\ntwo\n3\n4\n5\n");
+        assert_eq!(merged.conflicts, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn conflict_markers() {
+        let merged = merge_text(BASE, "1\nours\n3\n4\n5\n", "1\ntheirs\n3\n4\n5\n", MergeStrategy::Normal);
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            merged.text,
+            "1\n<<<<<<< ours\nours\n||||||| base\n2\n=======\ntheirs\n>>>>>>> theirs\n3\n4\n5\n"
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn resolve_by_strategy() {
+        let ours = "1\nours\n3\n4\n5\n";
+        let theirs = "1\ntheirs\n3\n4\n5\n";
+        assert_eq!(merge_text(BASE, ours, theirs, MergeStrategy::Ours).text, ours);
+        assert_eq!(merge_text(BASE, ours, theirs, MergeStrategy::Theirs).text, theirs);
+        let union = merge_text(BASE, ours, theirs, MergeStrategy::Union);
+        assert_eq!(union.text, "1\nours\ntheirs\n3\n4\n5\n");
+        assert_eq!(union.conflicts, 0);
+    }
+
+    #[wasm_bindgen_test]
+    fn insertions_at_end() {
+        let merged = merge_text(BASE, "1\n2\n3\n4\n5\n6\n", "0\n1\n2\n3\n4\n5\n", MergeStrategy::Normal);
+        assert_eq!(merged.text, "0\n1\n2\n3\n4\n5\n6\n");
+    }
+}
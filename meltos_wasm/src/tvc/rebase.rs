@@ -0,0 +1,521 @@
+use std::collections::BTreeSet;
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::object::commit::CommitHash;
+use meltos_tvc::object::ObjHash;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::abort::AbortSignal;
+use crate::error;
+use crate::error::{INVALID_OPERATION, TvcError};
+use crate::js_vec::JsVecRebaseStep;
+use crate::tvc::merge::{CONFLICTED, MERGE_STATE, MergeResult, MergeState, UP_TO_DATE};
+use crate::tvc::merge3::MergeStrategy;
+use crate::tvc::repository::Snapshot;
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::WasmTvcClient;
+
+pub const REBASED: &str = "rebased";
+
+pub const PICK: &str = "pick";
+pub const REWORD: &str = "reword";
+pub const SQUASH: &str = "squash";
+pub const FIXUP: &str = "fixup";
+pub const DROP: &str = "drop";
+
+pub(crate) const REBASE_STATE: &str = "REBASE_STATE";
+
+
+/// What to do with a commit while rebasing.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RebaseStep {
+    /// One of `pick`, `reword`, `squash`, `fixup` or `drop`.
+    pub action: String,
+    pub commit: String,
+    /// The new message for `reword`, or the message of the combined commit for `squash`.
+    pub message: Option<String>,
+}
+
+
+/// A rebase paused by conflicts, saved until it is continued or aborted.
+///
+/// The conflicts of the paused step are saved as a paused merge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RebaseState {
+    branch: String,
+    orig_head: String,
+    /// The commit rebased onto, from which `push` sends the commits.
+    onto: String,
+    /// The commit rewritten last, or the commit rebased onto if none has been.
+    current: String,
+    /// The commit whose files the working files reflect, except the conflicted ones.
+    working: String,
+    checked_out: bool,
+    /// The remaining steps, the first of which is the one paused.
+    steps: Vec<RebaseStep>,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Lists the commits which haven't been pushed yet as `pick` steps, oldest first.
+    ///
+    /// Only these commits can be rewritten, and the returned plan can be edited
+    /// and passed to [`WasmTvcClient::rebase`].
+    pub async fn rebase_plan(&self, branch_name: String) -> error::Result<JsVecRebaseStep> {
+        let local_commits = self.repository().read_local_commits(&BranchName(branch_name)).await?;
+        Ok(JsVecRebaseStep(local_commits
+            .into_iter()
+            .map(|commit_hash| RebaseStep {
+                action: PICK.to_string(),
+                commit: commit_hash.0.0,
+                message: None,
+            })
+            .collect()))
+    }
+
+    /// Replays the commits which haven't been pushed yet onto `onto` by following `plan`.
+    ///
+    /// `onto` is a branch name, a tag name or a commit hash, and defaults to the last pushed commit,
+    /// so that the commits are edited in place. `plan` is an array of steps
+    /// as returned by [`WasmTvcClient::rebase_plan`]; commits left out of it are dropped.
+    /// Steps whose commits `onto` already contains are skipped before the plan is validated.
+    /// A step which conflicts pauses the rebase; the conflicts are marked resolved
+    /// by [`WasmTvcClient::resolve_conflict`] as in a merge,
+    /// then [`WasmTvcClient::continue_rebase`] or [`WasmTvcClient::abort_rebase`] is called.
+    pub async fn rebase(&self, branch_name: String, onto: Option<String>, plan: JsValue, signal: Option<AbortSignal>) -> error::Result<MergeResult> {
+        self.observe_heads(None, self.abortable(signal, |client| async move {
+            let branch_name = BranchName(branch_name);
+            let repository = client.repository();
+            client.ensure_not_rebasing().await?;
+            client.ensure_not_merging().await?;
+            let Some(head) = repository.read_head(&branch_name).await? else {
+                return Err(TvcError::not_found("branch", &branch_name).into());
+            };
+            let checked_out = client.is_checked_out(&branch_name).await?;
+            if checked_out {
+                client.ensure_no_changes(&branch_name).await?;
+            }
+
+            let local_commits = repository.read_local_commits(&branch_name).await?;
+            let upstream = match local_commits.first() {
+                Some(first) => repository.read_commit(first).await?.parents.into_iter().next(),
+                None => Some(head.clone()),
+            };
+            let Some(upstream) = upstream else {
+                return Err(TvcError::new(
+                    INVALID_OPERATION,
+                    format!("can't rebase the initial commit: {branch_name}"),
+                ).into());
+            };
+            let onto = match onto {
+                Some(onto) => repository.resolve(&onto).await?,
+                None => upstream.clone(),
+            };
+
+            let steps: Vec<RebaseStep> = if plan.is_undefined() || plan.is_null() {
+                client.rebase_plan(branch_name.0.clone()).await?.0
+            } else {
+                serde_wasm_bindgen::from_value(plan).map_err(|e| TvcError::new(INVALID_OPERATION, e.to_string()))?
+            };
+            let onto_ancestors = repository.ancestors(&onto).await?;
+            let steps: Vec<RebaseStep> = steps
+                .into_iter()
+                .filter(|step| !onto_ancestors.contains(&CommitHash(ObjHash(step.commit.clone()))))
+                .collect();
+            validate_plan(&steps, &local_commits)?;
+
+            client.run_rebase(RebaseState {
+                branch: branch_name.0,
+                orig_head: head.0.0.clone(),
+                onto: onto.0.0.clone(),
+                working: head.0.0,
+                current: onto.0.0,
+                checked_out,
+                steps,
+            }).await
+        })).await
+    }
+
+    /// Continues the rebase paused by conflicts with the current content of the conflicted files.
+    ///
+    /// Fails if any conflict isn't marked resolved by [`WasmTvcClient::resolve_conflict`].
+    pub async fn continue_rebase(&self) -> error::Result<MergeResult> {
+        self.observe_heads(None, async {
+            let mut state = self.read_rebase_state().await?;
+            let (_, snapshot) = self.read_resolved_merge().await?;
+            self.repository().delete_state(MERGE_STATE).await?;
+
+            let step = state.steps.remove(0);
+            self.commit_step(&mut state, &step, &snapshot).await?;
+            state.working = state.current.clone();
+            self.run_rebase(state).await
+        }).await
+    }
+
+    /// Cancels the rebase paused by conflicts and restores the working files to the original head.
+    ///
+    /// The branch isn't moved until the rebase completes, so only the working files are restored.
+    pub async fn abort_rebase(&self) -> error::Result {
+        self.observe_heads(None, async {
+            let state = self.read_rebase_state().await?;
+            let repository = self.repository();
+            let orig = repository.read_snapshot(&CommitHash(ObjHash(state.orig_head))).await?;
+            let working = repository.read_snapshot(&CommitHash(ObjHash(state.working))).await?;
+            let paused: Option<MergeState> = repository.read_state(MERGE_STATE).await?;
+            let paused_paths = paused.iter().flat_map(|paused| paused
+                .merged
+                .keys()
+                .chain(paused.conflicts.iter().map(|conflict| &conflict.path)));
+            let paths: BTreeSet<String> = orig
+                .keys()
+                .chain(working.keys())
+                .chain(paused_paths)
+                .cloned()
+                .collect();
+            self.restore_working_files(&orig, &paths).await?;
+            if paused.is_some() {
+                repository.delete_state(MERGE_STATE).await?;
+            }
+            repository.delete_state(REBASE_STATE).await
+        }).await
+    }
+
+    pub async fn is_rebasing(&self) -> error::Result<bool> {
+        Ok(self.repository().read_state::<RebaseState>(REBASE_STATE).await?.is_some())
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Runs the remaining steps, and moves the branch once all of them are done.
+    async fn run_rebase(&self, mut state: RebaseState) -> error::Result<MergeResult> {
+        let repository = self.repository();
+        while let Some(step) = state.steps.first().cloned() {
+            if step.action == DROP {
+                state.steps.remove(0);
+                continue;
+            }
+            let commit_hash = CommitHash(ObjHash(step.commit.clone()));
+            let commit = repository.read_commit(&commit_hash).await?;
+            let base = self.read_first_parent_snapshot(&commit.parents).await?;
+            let ours = repository.read_snapshot(&CommitHash(ObjHash(state.current.clone()))).await?;
+            let theirs = repository.read_snapshot(&commit_hash).await?;
+            let merged = self.merge_snapshots(&base, &ours, &theirs, MergeStrategy::Normal).await?;
+
+            if merged.conflicts.is_empty() {
+                state.steps.remove(0);
+                self.commit_step(&mut state, &step, &merged.snapshot).await?;
+                continue;
+            }
+            if !state.checked_out {
+                return Err(TvcError::new(INVALID_OPERATION, format!(
+                    "conflicts can be resolved only on the checked out branch: {}",
+                    state.branch
+                )).into());
+            }
+            let working = repository.read_snapshot(&CommitHash(ObjHash(state.working.clone()))).await?;
+            self.write_working_files(&working, &ours).await?;
+            let conflicts = self.write_merged_working_files(&ours, &merged).await?;
+            let current = CommitHash(ObjHash(state.current.clone()));
+            let branch_name = BranchName(state.branch.clone());
+            let message = commit.text.0;
+            self.pause_merge(&branch_name, vec![current], message, None, merged.snapshot, conflicts.clone()).await?;
+            state.working = state.current.clone();
+            repository.write_state(REBASE_STATE, &state).await?;
+            return Ok(MergeResult {
+                kind: CONFLICTED.to_string(),
+                commit: None,
+                conflicts,
+            });
+        }
+
+        let branch_name = BranchName(state.branch.clone());
+        let working = CommitHash(ObjHash(state.working.clone()));
+        let new_head = CommitHash(ObjHash(state.current.clone()));
+        if self.is_rebasing().await? {
+            repository.delete_state(REBASE_STATE).await?;
+        }
+        if repository.read_head(&branch_name).await?.as_ref() == Some(&new_head) {
+            return Ok(MergeResult::new(UP_TO_DATE, Some(&new_head)));
+        }
+        let onto = CommitHash(ObjHash(state.onto.clone()));
+        let local_commits = repository.commits_between(Some(&onto), &new_head).await?;
+        repository.write_local_commits(&branch_name, local_commits).await?;
+        repository.write_head(&branch_name, &new_head).await?;
+        if state.checked_out {
+            let working = repository.read_snapshot(&working).await?;
+            let new = repository.read_snapshot(&new_head).await?;
+            self.write_working_files(&working, &new).await?;
+        }
+        Ok(MergeResult::new(REBASED, Some(&new_head)))
+    }
+
+
+    /// Writes the commit of the step whose files are `snapshot`, replacing the current commit
+    /// if the step is `squash` or `fixup`.
+    async fn commit_step(&self, state: &mut RebaseState, step: &RebaseStep, snapshot: &Snapshot) -> error::Result {
+        let repository = self.repository();
+        let current_hash = CommitHash(ObjHash(state.current.clone()));
+        let text = repository.read_commit(&CommitHash(ObjHash(step.commit.clone()))).await?.text.0;
+        // The author and the timestamp of the rewritten commits are kept.
+        let (parents, message) = match step.action.as_str() {
+            SQUASH | FIXUP => {
+                let current = repository.read_commit(&current_hash).await?;
+                let message = if step.action == FIXUP {
+                    current.text.0
+                } else {
+                    let info = CommitInfo::parse(&current.text.0);
+                    let message = match step.message.as_ref() {
+                        Some(message) => CommitInfo::parse(message).message,
+                        None => format!("{}\n\n{}", info.message, CommitInfo::parse(&text).message),
+                    };
+                    info.with_message(message).to_text()
+                };
+                (current.parents, message)
+            }
+            _ => {
+                let message = match step.message.clone() {
+                    Some(message) => CommitInfo::parse(&text).with_message(CommitInfo::parse(&message).message).to_text(),
+                    None => text,
+                };
+                (vec![current_hash], message)
+            }
+        };
+        state.current = repository.write_detached_commit(parents, message, snapshot).await?.0.0;
+        Ok(())
+    }
+
+
+    pub(crate) async fn ensure_not_rebasing(&self) -> error::Result {
+        if self.is_rebasing().await? {
+            Err(TvcError::new(INVALID_OPERATION, "a rebase is in progress; continue or abort it first").into())
+        } else {
+            Ok(())
+        }
+    }
+
+
+    async fn read_rebase_state(&self) -> error::Result<RebaseState> {
+        self
+            .repository()
+            .read_state(REBASE_STATE)
+            .await?
+            .ok_or_else(|| TvcError::new(INVALID_OPERATION, "no rebase is in progress").into())
+    }
+}
+
+
+/// Checks that the plan rewrites only the commits which haven't been pushed yet
+/// and that `squash` and `fixup` have a commit to be combined into.
+fn validate_plan(steps: &[RebaseStep], local_commits: &[CommitHash]) -> error::Result {
+    let mut picked = false;
+    let mut seen = BTreeSet::new();
+    for step in steps {
+        if !local_commits.iter().any(|commit_hash| commit_hash.0.0 == step.commit) {
+            return Err(TvcError::new(
+                INVALID_OPERATION,
+                format!("only unpushed commits can be rebased: {}", step.commit),
+            ).into());
+        }
+        if !seen.insert(step.commit.as_str()) {
+            return Err(TvcError::new(
+                INVALID_OPERATION,
+                format!("commit appears twice in the plan: {}", step.commit),
+            ).into());
+        }
+        match step.action.as_str() {
+            PICK => picked = true,
+            REWORD if step.message.is_none() => {
+                return Err(TvcError::new(INVALID_OPERATION, format!("reword needs a message: {}", step.commit)).into());
+            }
+            REWORD => picked = true,
+            SQUASH | FIXUP if !picked => {
+                return Err(TvcError::new(
+                    INVALID_OPERATION,
+                    format!("no commit to {} into: {}", step.action, step.commit),
+                ).into());
+            }
+            SQUASH | FIXUP | DROP => {}
+            action => return Err(TvcError::new(INVALID_OPERATION, format!("unknown rebase action: {action}")).into()),
+        }
+    }
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use meltos_tvc::object::commit::CommitHash;
+    use wasm_bindgen::JsValue;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, diverged_client, tvc_client, write_files};
+    use crate::tvc::merge::{CONFLICTED, MergeResult};
+    use crate::tvc::rebase::{DROP, FIXUP, PICK, REBASED, RebaseStep, REWORD, SQUASH};
+    use crate::tvc::signature::CommitInfo;
+    use crate::tvc::WasmTvcClient;
+
+    fn branch(name: &str) -> BranchName {
+        BranchName(name.to_string())
+    }
+
+    fn step(action: &str, commit: &CommitHash, message: Option<&str>) -> RebaseStep {
+        RebaseStep {
+            action: action.to_string(),
+            commit: commit.0.0.clone(),
+            message: message.map(str::to_string),
+        }
+    }
+
+    fn plan(steps: &[RebaseStep]) -> JsValue {
+        serde_wasm_bindgen::to_value(steps).unwrap()
+    }
+
+    /// Marks the commits of the branch so far as pushed.
+    async fn mark_pushed(client: &WasmTvcClient, branch_name: &str) {
+        client.repository().write_local_commits(&branch(branch_name), Vec::new()).await.unwrap();
+    }
+
+    async fn head(client: &WasmTvcClient, branch_name: &str) -> CommitHash {
+        client.repository().read_head(&branch(branch_name)).await.unwrap().unwrap()
+    }
+
+    async fn message(client: &WasmTvcClient, commit_hash: &CommitHash) -> String {
+        CommitInfo::parse(&client.repository().read_commit(commit_hash).await.unwrap().text.0).message
+    }
+
+    async fn rebase_onto_feature(client: &WasmTvcClient) -> MergeResult {
+        client
+            .rebase("main".to_string(), Some("feature".to_string()), JsValue::UNDEFINED, None)
+            .await
+            .unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn edit_commits_in_place() {
+        let client = tvc_client("rebase_in_place").await;
+        let pushed = commit_files(&client, "main", &[("workspace/a.txt", "0")]).await;
+        mark_pushed(&client, "main").await;
+        let first = commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        let second = commit_files(&client, "main", &[("workspace/b.txt", "2")]).await;
+        let third = commit_files(&client, "main", &[("workspace/c.txt", "3")]).await;
+        let fourth = commit_files(&client, "main", &[("workspace/d.txt", "4")]).await;
+        let steps: Vec<String> = client
+            .rebase_plan("main".to_string())
+            .await
+            .unwrap()
+            .0
+            .into_iter()
+            .map(|step| step.commit)
+            .collect();
+        assert_eq!(steps, vec![first.0.0.clone(), second.0.0.clone(), third.0.0.clone(), fourth.0.0.clone()]);
+
+        let result = client.rebase("main".to_string(), None, plan(&[
+            step(REWORD, &first, Some("renamed")),
+            step(FIXUP, &second, None),
+            step(DROP, &third, None),
+            step(PICK, &fourth, None),
+        ]), None).await.unwrap();
+        assert_eq!(result.kind, REBASED);
+
+        let new_head = head(&client, "main").await;
+        assert_eq!(result.commit, Some(new_head.0.0.clone()));
+        let repository = client.repository();
+        let squashed = repository.read_commit(&new_head).await.unwrap().parents[0].clone();
+        assert_eq!(repository.read_commit(&squashed).await.unwrap().parents, vec![pushed]);
+        assert_eq!(message(&client, &squashed).await, "renamed");
+        assert_eq!(repository.read_local_commits(&branch("main")).await.unwrap(), vec![squashed, new_head]);
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), Some(b"2".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/c.txt").await.unwrap(), None);
+        assert_eq!(client.fs.read_file("workspace/d.txt").await.unwrap(), Some(b"4".to_vec()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn rebase_onto_other_branch() {
+        let client = diverged_client(
+            "rebase_onto",
+            &[("workspace/a.txt", "a")],
+            &[("workspace/b.txt", "b")],
+            &[("workspace/c.txt", "c")],
+        ).await;
+        let onto = head(&client, "feature").await;
+
+        let result = rebase_onto_feature(&client).await;
+        assert_eq!(result.kind, REBASED);
+        let new_head = head(&client, "main").await;
+        assert_eq!(client.repository().read_commit(&new_head).await.unwrap().parents, vec![onto]);
+        assert_eq!(client.repository().read_local_commits(&branch("main")).await.unwrap(), vec![new_head]);
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), Some(b"b".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/c.txt").await.unwrap(), Some(b"c".to_vec()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn refuse_squash_into_skipped_commit() {
+        let client = tvc_client("rebase_skipped_squash").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "0")]).await;
+        mark_pushed(&client, "main").await;
+        let first = commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        client.create_branch("feature".to_string(), "main".to_string()).await.unwrap();
+        let second = commit_files(&client, "main", &[("workspace/a.txt", "2")]).await;
+
+        let steps = plan(&[step(PICK, &first, None), step(SQUASH, &second, None)]);
+        assert!(client.rebase("main".to_string(), Some("feature".to_string()), steps, None).await.is_err());
+        assert_eq!(head(&client, "main").await, second);
+    }
+
+    #[wasm_bindgen_test]
+    async fn continue_after_resolving_conflicts() {
+        let client = diverged_client(
+            "rebase_continue",
+            &[("workspace/a.txt", "base\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n")],
+        ).await;
+        let onto = head(&client, "feature").await;
+
+        let result = rebase_onto_feature(&client).await;
+        assert_eq!(result.kind, CONFLICTED);
+        assert_eq!(result.conflicts[0].path, "workspace/a.txt");
+        assert!(client.is_rebasing().await.unwrap());
+        assert!(client.continue_rebase().await.is_err());
+        assert!(client.conclude_merge(None).await.is_err());
+
+        write_files(&client, &[("workspace/a.txt", "resolved\n")]).await;
+        client.resolve_conflict("workspace/a.txt".to_string()).await.unwrap();
+        let result = client.continue_rebase().await.unwrap();
+        assert_eq!(result.kind, REBASED);
+        let new_head = head(&client, "main").await;
+        assert_eq!(client.repository().read_commit(&new_head).await.unwrap().parents, vec![onto]);
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"resolved\n".to_vec()));
+        assert!(!client.is_rebasing().await.unwrap());
+        assert!(!client.is_merging().await.unwrap());
+    }
+
+    #[wasm_bindgen_test]
+    async fn abort_restores_original_head() {
+        let client = diverged_client(
+            "rebase_abort",
+            &[("workspace/a.txt", "base\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n"), ("workspace/b.txt", "b\n")],
+        ).await;
+        let orig_head = head(&client, "main").await;
+
+        let result = rebase_onto_feature(&client).await;
+        assert_eq!(result.kind, CONFLICTED);
+
+        client.abort_rebase().await.unwrap();
+        assert_eq!(head(&client, "main").await, orig_head);
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"ours\n".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), None);
+        assert!(!client.is_rebasing().await.unwrap());
+        assert!(!client.is_merging().await.unwrap());
+    }
+}
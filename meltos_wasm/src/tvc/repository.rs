@@ -0,0 +1,447 @@
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::{FilePath, FileSystem};
+use meltos_tvc::io::atomic::head::HeadIo;
+use meltos_tvc::io::atomic::local_commits::LocalCommitsIo;
+use meltos_tvc::io::atomic::object::ObjIo;
+use meltos_tvc::io::atomic::staging::StagingIo;
+use meltos_tvc::io::atomic::work_branch::WorkingIo;
+use meltos_tvc::io::trace_tree::TraceTreeIo;
+use meltos_tvc::object::commit::{CommitHash, CommitObj, CommitText};
+use meltos_tvc::object::delete::DeleteObj;
+use meltos_tvc::object::file::FileObj;
+use meltos_tvc::object::local_commits::LocalCommitsObj;
+use meltos_tvc::object::tree::{TreeHash, TreeObj};
+use meltos_tvc::object::{AsMeta, CompressedBuf, Obj, ObjHash};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error;
+use crate::error::{CORRUPTED, IntoJsResult, TvcError};
+use crate::file_system::WasmFileSystem;
+use crate::tvc::tag::Tag;
+
+/// The directory in which the states which tvc itself doesn't know are saved.
+const STATE_DIR: &str = ".meltos/wasm";
+
+/// The state directory in which each tag is saved as a ref named after it.
+const TAGS: &str = "tags";
+
+
+/// The files committed at a commit, keyed by their path.
+pub type Snapshot = BTreeMap<String, ObjHash>;
+
+
+/// The staged files keyed by their path.
+///
+/// `None` means that the deletion of the file is staged.
+pub type StagedChanges = BTreeMap<String, Option<ObjHash>>;
+
+
+/// Reads and writes the branches, commits and objects stored by tvc.
+#[derive(Debug, Clone)]
+pub struct Repository {
+    fs: WasmFileSystem,
+}
+
+
+impl Repository {
+    #[inline(always)]
+    pub fn new(fs: WasmFileSystem) -> Self {
+        Self {
+            fs
+        }
+    }
+
+    /// Resolves a branch name, a tag name or a commit hash into a commit hash.
+    ///
+    /// The hash of an existing commit is resolved as is,
+    /// and a branch takes precedence over a tag of the same name.
+    pub async fn resolve(&self, branch_or_commit: &str) -> error::Result<CommitHash> {
+        let commit_hash = CommitHash(ObjHash(branch_or_commit.to_string()));
+        if looks_like_hash(branch_or_commit) && self.exists_commit(&commit_hash).await {
+            return Ok(commit_hash);
+        }
+        let branch_name = BranchName(branch_or_commit.to_string());
+        if let Some(head) = self.read_head(&branch_name).await? {
+            return Ok(head);
+        }
+        if let Some(tag) = self.read_tag(branch_or_commit).await? {
+            return Ok(CommitHash(ObjHash(tag.commit)));
+        }
+        Err(TvcError::not_found("branch, tag or commit", &branch_or_commit).into())
+    }
+
+    #[inline(always)]
+    pub async fn read_head(&self, branch_name: &BranchName) -> error::Result<Option<CommitHash>> {
+        HeadIo::new(self.fs.clone())
+            .read(branch_name)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn write_head(&self, branch_name: &BranchName, commit_hash: &CommitHash) -> error::Result {
+        HeadIo::new(self.fs.clone())
+            .write(branch_name, commit_hash)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn delete_head(&self, branch_name: &BranchName) -> error::Result {
+        HeadIo::new(self.fs.clone())
+            .delete(branch_name)
+            .await
+            .into_js_result()
+    }
+
+    /// Reads the commits of the branch which have not been pushed yet, oldest first.
+    pub async fn read_local_commits(&self, branch_name: &BranchName) -> error::Result<Vec<CommitHash>> {
+        Ok(LocalCommitsIo::new(self.fs.clone())
+            .read(branch_name)
+            .await
+            .into_js_result()?
+            .map(|local_commits| local_commits.0)
+            .unwrap_or_default())
+    }
+
+    #[inline(always)]
+    pub async fn write_local_commits(&self, branch_name: &BranchName, commits: Vec<CommitHash>) -> error::Result {
+        LocalCommitsIo::new(self.fs.clone())
+            .write(&LocalCommitsObj(commits), branch_name)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn delete_local_commits(&self, branch_name: &BranchName) -> error::Result {
+        LocalCommitsIo::new(self.fs.clone())
+            .delete(branch_name)
+            .await
+            .into_js_result()
+    }
+
+    /// Reads the branch currently unzipped into the workspace.
+    #[inline(always)]
+    pub async fn read_working_branch(&self) -> error::Result<Option<BranchName>> {
+        WorkingIo::new(self.fs.clone())
+            .read()
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn write_working_branch(&self, branch_name: &BranchName) -> error::Result {
+        WorkingIo::new(self.fs.clone())
+            .write(branch_name)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn read_commit(&self, commit_hash: &CommitHash) -> error::Result<CommitObj> {
+        ObjIo::new(self.fs.clone())
+            .read_to_commit(commit_hash)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn exists_commit(&self, commit_hash: &CommitHash) -> bool {
+        self.read_commit(commit_hash).await.is_ok()
+    }
+
+    /// Reads all files committed at `commit_hash` from its trace.
+    pub async fn read_snapshot(&self, commit_hash: &CommitHash) -> error::Result<Snapshot> {
+        let tree = TraceTreeIo::new(self.fs.clone())
+            .read(commit_hash)
+            .await
+            .into_js_result()?;
+        Ok(into_snapshot(tree))
+    }
+
+    /// Reads the object as it is stored, which is the form sent in a bundle.
+    #[inline(always)]
+    pub async fn read_compressed_obj(&self, obj_hash: &ObjHash) -> error::Result<Option<CompressedBuf>> {
+        ObjIo::new(self.fs.clone())
+            .read(obj_hash)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn read_obj(&self, obj_hash: &ObjHash) -> error::Result<Option<Obj>> {
+        ObjIo::new(self.fs.clone())
+            .read_obj(obj_hash)
+            .await
+            .into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn read_file_obj(&self, obj_hash: &ObjHash) -> error::Result<Vec<u8>> {
+        let file = ObjIo::new(self.fs.clone())
+            .read_to_file(obj_hash)
+            .await
+            .into_js_result()?;
+        Ok(file.0)
+    }
+
+    /// Reads the paths changed by the commit.
+    pub async fn read_changed_paths(&self, commit: &CommitObj) -> error::Result<Vec<String>> {
+        let tree = self.read_tree(&commit.committed_objs_tree).await?;
+        Ok(tree.into_keys().collect())
+    }
+
+    #[inline(always)]
+    pub async fn read_tree(&self, tree_hash: &TreeHash) -> error::Result<Snapshot> {
+        let tree = ObjIo::new(self.fs.clone())
+            .read_to_tree(tree_hash)
+            .await
+            .into_js_result()?;
+        Ok(into_snapshot(tree))
+    }
+
+    /// Reads the files in the staging area.
+    pub async fn read_staged_changes(&self) -> error::Result<StagedChanges> {
+        let Some(tree) = StagingIo::new(self.fs.clone())
+            .read()
+            .await
+            .into_js_result()? else {
+            return Ok(StagedChanges::new());
+        };
+
+        let obj_io = ObjIo::new(self.fs.clone());
+        let mut changes = StagedChanges::new();
+        for (file_path, obj_hash) in tree.0 {
+            let obj = obj_io.try_read_obj(&obj_hash).await.into_js_result()?;
+            changes.insert(file_path.0, (!matches!(obj, Obj::Delete(_))).then_some(obj_hash));
+        }
+        Ok(changes)
+    }
+
+    /// Stages the file object for the path, or removes the path from the staging area if `obj_hash` is `None`.
+    pub async fn write_staged_obj(&self, path: &str, obj_hash: Option<ObjHash>) -> error::Result {
+        let staging = StagingIo::new(self.fs.clone());
+        let mut tree = staging
+            .read()
+            .await
+            .into_js_result()?
+            .unwrap_or_else(|| TreeObj(Default::default()));
+        match obj_hash {
+            Some(obj_hash) => tree.0.insert(FilePath(path.to_string()), obj_hash),
+            None => tree.0.remove(&FilePath(path.to_string())),
+        };
+        staging.write_tree(&tree).await.into_js_result()
+    }
+
+    /// Stages the deletion of the file whose object at the head is `obj_hash`.
+    pub async fn write_staged_deletion(&self, path: &str, obj_hash: &ObjHash) -> error::Result {
+        let delete_hash = self.write_obj(&DeleteObj(obj_hash.clone())).await?;
+        self.write_staged_obj(path, Some(delete_hash)).await
+    }
+
+    #[inline(always)]
+    pub async fn write_file_obj(&self, buf: Vec<u8>) -> error::Result<ObjHash> {
+        self.write_obj(&FileObj(buf)).await
+    }
+
+    /// Writes a commit whose files are `snapshot` and moves the head of the branch to it.
+    ///
+    /// The commit is appended to the local commits of the branch so that `push` sends it.
+    pub async fn write_commit(
+        &self,
+        branch_name: &BranchName,
+        parents: Vec<CommitHash>,
+        text: String,
+        snapshot: &Snapshot,
+    ) -> error::Result<CommitHash> {
+        let commit_hash = self.write_detached_commit(parents, text, snapshot).await?;
+        self.write_head(branch_name, &commit_hash).await?;
+        let mut local_commits = self.read_local_commits(branch_name).await?;
+        local_commits.push(commit_hash.clone());
+        self.write_local_commits(branch_name, local_commits).await?;
+        Ok(commit_hash)
+    }
+
+    /// Writes a commit whose files are `snapshot` without moving any branch.
+    pub async fn write_detached_commit(
+        &self,
+        parents: Vec<CommitHash>,
+        text: String,
+        snapshot: &Snapshot,
+    ) -> error::Result<CommitHash> {
+        let base = match parents.first() {
+            Some(parent) => self.read_snapshot(parent).await?,
+            None => Snapshot::new(),
+        };
+        let mut changes = Snapshot::new();
+        for (path, obj_hash) in snapshot {
+            if base.get(path) != Some(obj_hash) {
+                changes.insert(path.clone(), obj_hash.clone());
+            }
+        }
+        for (path, obj_hash) in base.iter().filter(|(path, _)| !snapshot.contains_key(*path)) {
+            let delete_hash = self.write_obj(&DeleteObj(obj_hash.clone())).await?;
+            changes.insert(path.clone(), delete_hash);
+        }
+
+        let committed_objs_tree = TreeHash(self.write_obj(&into_tree(&changes)).await?);
+        let commit_hash = CommitHash(self.write_obj(&CommitObj {
+            parents,
+            text: CommitText(text),
+            committed_objs_tree,
+        }).await?);
+        TraceTreeIo::new(self.fs.clone())
+            .write(&into_tree(snapshot), &commit_hash)
+            .await
+            .into_js_result()?;
+        Ok(commit_hash)
+    }
+
+    /// Finds the nearest common ancestor of two commits.
+    pub async fn merge_base(&self, lhs: &CommitHash, rhs: &CommitHash) -> error::Result<Option<CommitHash>> {
+        let ancestors = self.ancestors(lhs).await?;
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::from([rhs.clone()]);
+        while let Some(commit_hash) = pending.pop_front() {
+            if ancestors.contains(&commit_hash) {
+                return Ok(Some(commit_hash));
+            }
+            if visited.insert(commit_hash.clone()) {
+                pending.extend(self.read_commit(&commit_hash).await?.parents);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads all commits reachable from `commit_hash`, including itself.
+    pub async fn ancestors(&self, commit_hash: &CommitHash) -> error::Result<HashSet<CommitHash>> {
+        let mut ancestors = HashSet::new();
+        let mut pending = vec![commit_hash.clone()];
+        while let Some(commit_hash) = pending.pop() {
+            if ancestors.insert(commit_hash.clone()) {
+                pending.extend(self.read_commit(&commit_hash).await?.parents);
+            }
+        }
+        Ok(ancestors)
+    }
+
+    /// Reads the commits reachable from `to` but not from `from`, oldest first.
+    pub async fn commits_between(&self, from: Option<&CommitHash>, to: &CommitHash) -> error::Result<Vec<CommitHash>> {
+        let excluded = match from {
+            Some(from) => self.ancestors(from).await?,
+            None => HashSet::new(),
+        };
+        let mut commits = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = VecDeque::from([to.clone()]);
+        while let Some(commit_hash) = pending.pop_front() {
+            if excluded.contains(&commit_hash) || !visited.insert(commit_hash.clone()) {
+                continue;
+            }
+            pending.extend(self.read_commit(&commit_hash).await?.parents);
+            commits.push(commit_hash);
+        }
+        commits.reverse();
+        Ok(commits)
+    }
+
+    #[inline(always)]
+    pub async fn read_tag(&self, name: &str) -> error::Result<Option<Tag>> {
+        self.read_state(&tag_state_name(name)).await
+    }
+
+    #[inline(always)]
+    pub async fn write_tag(&self, tag: &Tag) -> error::Result {
+        self.write_state(&tag_state_name(&tag.name), tag).await
+    }
+
+    #[inline(always)]
+    pub async fn delete_tag(&self, name: &str) -> error::Result {
+        self.delete_state(&tag_state_name(name)).await
+    }
+
+    /// Reads all tags sorted by their name.
+    pub async fn read_tags(&self) -> error::Result<Vec<Tag>> {
+        let tags_dir = state_path(TAGS);
+        let files = self.fs.all_files_in(&tags_dir).await.into_js_result()?;
+        let mut tags = Vec::with_capacity(files.len());
+        for file in files {
+            let name = file
+                .trim_start_matches('/')
+                .trim_start_matches(&tags_dir)
+                .trim_start_matches('/');
+            if let Some(tag) = self.read_tag(name).await? {
+                tags.push(tag);
+            }
+        }
+        tags.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+        Ok(tags)
+    }
+
+    /// Reads a state saved as json in the repository.
+    pub async fn read_state<T: DeserializeOwned>(&self, name: &str) -> error::Result<Option<T>> {
+        let Some(buf) = self.fs.read_file(&state_path(name)).await.into_js_result()? else {
+            return Ok(None);
+        };
+        Ok(Some(serde_json::from_slice(&buf).map_err(|e| TvcError::new(CORRUPTED, e.to_string()))?))
+    }
+
+    pub async fn write_state<T: Serialize>(&self, name: &str, state: &T) -> error::Result {
+        let buf = serde_json::to_vec(state).map_err(|e| TvcError::new(CORRUPTED, e.to_string()))?;
+        self.fs.write_file(&state_path(name), &buf).await.into_js_result()
+    }
+
+    #[inline(always)]
+    pub async fn delete_state(&self, name: &str) -> error::Result {
+        self.fs.delete(&state_path(name)).await.into_js_result()
+    }
+
+    async fn write_obj(&self, obj: &impl AsMeta) -> error::Result<ObjHash> {
+        let meta = obj.as_meta().into_js_result()?;
+        ObjIo::new(self.fs.clone())
+            .write_obj(obj)
+            .await
+            .into_js_result()?;
+        Ok(meta.hash)
+    }
+}
+
+
+/// Whether `name` has the form of an object hash, a sha1 or sha256 digest in hex.
+#[inline]
+pub fn looks_like_hash(name: &str) -> bool {
+    matches!(name.len(), 40 | 64) && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+
+fn into_snapshot(tree: TreeObj) -> Snapshot {
+    tree
+        .0
+        .into_iter()
+        .map(|(file_path, obj_hash)| (file_path.0, obj_hash))
+        .collect()
+}
+
+
+#[inline]
+pub fn into_tree(snapshot: &Snapshot) -> TreeObj {
+    TreeObj(snapshot
+        .iter()
+        .map(|(path, obj_hash)| (FilePath(path.clone()), obj_hash.clone()))
+        .collect())
+}
+
+
+#[inline]
+fn tag_state_name(name: &str) -> String {
+    format!("{TAGS}/{name}")
+}
+
+
+#[inline]
+fn state_path(name: &str) -> String {
+    format!("{STATE_DIR}/{name}")
+}
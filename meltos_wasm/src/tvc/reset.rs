@@ -0,0 +1,250 @@
+use std::collections::BTreeSet;
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{classify, IntoJsResult, NOT_FOUND, TvcError};
+use crate::progress::{PhaseProgress, WRITING_FILES};
+use crate::tvc::repository::Snapshot;
+use crate::tvc::status::UNTRACKED;
+use crate::tvc::WasmTvcClient;
+use crate::tvc::worktree::is_under;
+
+/// How far [`WasmTvcClient::reset`] rolls a branch back.
+#[wasm_bindgen]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum ResetMode {
+    /// Moves only the head.
+    Soft,
+    /// Moves the head and clears the staging area.
+    #[default]
+    Mixed,
+    /// Moves the head, clears the staging area and rewrites the working files.
+    Hard,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Discards the working changes of the file, or of the files under the directory,
+    /// by writing back their content committed at `from`.
+    ///
+    /// `from` is a branch name or a commit hash, the head of the checked out branch by default.
+    /// Tracked files which don't exist at `from` are deleted, and untracked files are left as they are.
+    pub async fn restore(&self, path: String, from: Option<String>) -> error::Result {
+        let repository = self.repository();
+        let snapshot = match from {
+            Some(from) => repository.read_snapshot(&repository.resolve(&from).await?).await?,
+            None => {
+                let branch_name = self.checked_out_branch().await?;
+                match repository.read_head(&branch_name).await? {
+                    Some(head) => repository.read_snapshot(&head).await?,
+                    None => Snapshot::new(),
+                }
+            }
+        };
+
+        let tracked = self.tracked_paths().await?;
+        let paths: BTreeSet<String> = snapshot
+            .keys()
+            .chain(tracked.iter())
+            .filter(|file| is_under(file, &path))
+            .cloned()
+            .collect();
+        if paths.is_empty() {
+            return Err(TvcError::new(NOT_FOUND, format!("pathspec did not match any file: {path}")).into());
+        }
+        self.restore_working_files(&snapshot, &paths).await
+    }
+
+    /// Moves the head of the branch to `commit`, a branch name or a commit hash.
+    ///
+    /// The local commits which are no longer reachable from the head are discarded,
+    /// so `push` sends only the commits reachable from `commit`;
+    /// moving forward adds the commits passed over unless the remote already has them.
+    /// The staging area and the working files belong to the checked out branch,
+    /// so `mode` other than soft has effects only if the branch is checked out.
+    pub async fn reset(&self, branch_name: String, commit: String, mode: Option<ResetMode>) -> error::Result {
+        self.observe_heads(None, async {
+            let branch_name = BranchName(branch_name);
+            let repository = self.repository();
+            self.ensure_not_merging().await?;
+            let Some(head) = repository.read_head(&branch_name).await? else {
+                return Err(TvcError::not_found("branch", &branch_name).into());
+            };
+            let target = repository.resolve(&commit).await?;
+            let mode = mode.unwrap_or_default();
+            let checked_out = self.is_checked_out(&branch_name).await?;
+
+            // Collect the changed files before the head moves, since they are relative to it.
+            let changed: BTreeSet<String> = if checked_out && mode == ResetMode::Hard {
+                self
+                    .status_entries(&branch_name)
+                    .await?
+                    .into_iter()
+                    .filter(|entry| entry.status != UNTRACKED)
+                    .map(|entry| entry.path)
+                    .collect()
+            } else {
+                BTreeSet::new()
+            };
+
+            let reachable = repository.ancestors(&target).await?;
+            let remote_commits = self.remote_commits().await?;
+            let mut local_commits = repository.read_local_commits(&branch_name).await?;
+            local_commits.retain(|commit_hash| reachable.contains(commit_hash));
+            for commit_hash in repository.commits_between(Some(&head), &target).await? {
+                if !local_commits.contains(&commit_hash) && !remote_commits.contains(&commit_hash) {
+                    local_commits.push(commit_hash);
+                }
+            }
+            repository.write_local_commits(&branch_name, local_commits).await?;
+            repository.write_head(&branch_name, &target).await?;
+
+            if !checked_out || mode == ResetMode::Soft {
+                return Ok(());
+            }
+            self.tvc.un_stage_all().await.map_err(classify)?;
+            if mode == ResetMode::Hard {
+                let old = repository.read_snapshot(&head).await?;
+                let new = repository.read_snapshot(&target).await?;
+                let paths: BTreeSet<String> = old
+                    .keys()
+                    .chain(new.keys())
+                    .filter(|path| old.get(*path) != new.get(*path))
+                    .cloned()
+                    .chain(changed)
+                    .collect();
+                self.restore_working_files(&new, &paths).await?;
+            }
+            Ok(())
+        }).await
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Writes each of `paths` with its content in `snapshot`, or deletes it if it isn't in `snapshot`.
+    pub(crate) async fn restore_working_files(&self, snapshot: &Snapshot, paths: &BTreeSet<String>) -> error::Result {
+        let repository = self.repository();
+        let mut progress = PhaseProgress::new(self.progress.as_ref(), WRITING_FILES, paths.len());
+        for path in paths {
+            match snapshot.get(path) {
+                Some(obj_hash) => {
+                    let buf = repository.read_file_obj(obj_hash).await?;
+                    self.fs.write_file(path, &buf).await.into_js_result()?;
+                    progress.advance(1, buf.len(), Some(path));
+                }
+                None => {
+                    if self.fs.read_file(path).await.into_js_result()?.is_some() {
+                        self.fs.delete(path).await.into_js_result()?;
+                    }
+                    progress.advance(1, 0, Some(path));
+                }
+            }
+        }
+        Ok(())
+    }
+
+
+    /// Reads the files committed at the head of the checked out branch or staged.
+    async fn tracked_paths(&self) -> error::Result<BTreeSet<String>> {
+        let Some(branch_name) = self.repository().read_working_branch().await? else {
+            return Ok(BTreeSet::new());
+        };
+        let staged = self.staged_tree(&branch_name).await?;
+        let head = self.head_tree(&branch_name).await?;
+        Ok(staged.into_keys().chain(head.into_keys()).collect())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, tvc_client, write_files};
+    use crate::tvc::reset::ResetMode;
+
+    fn main() -> BranchName {
+        BranchName("main".to_string())
+    }
+
+    #[wasm_bindgen_test]
+    async fn reset_back_and_forward_keeps_local_commits() {
+        let client = tvc_client("reset_local_commits").await;
+        let first = commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        let second = commit_files(&client, "main", &[("workspace/a.txt", "2")]).await;
+        let repository = client.repository();
+        let local_commits = repository.read_local_commits(&main()).await.unwrap();
+
+        client.reset("main".to_string(), first.0.0.clone(), Some(ResetMode::Soft)).await.unwrap();
+        assert_eq!(repository.read_head(&main()).await.unwrap(), Some(first));
+        assert!(!repository.read_local_commits(&main()).await.unwrap().contains(&second));
+
+        client.reset("main".to_string(), second.0.0.clone(), Some(ResetMode::Soft)).await.unwrap();
+        assert_eq!(repository.read_local_commits(&main()).await.unwrap(), local_commits);
+    }
+
+    #[wasm_bindgen_test]
+    async fn reset_forward_skips_remote_commits() {
+        let client = tvc_client("reset_remote_commits").await;
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        let repository = client.repository();
+        // A fetched branch has no local commits.
+        let snapshot = repository.read_snapshot(&head).await.unwrap();
+        let fetched = repository
+            .write_detached_commit(vec![head], "fetched\n\nTimestamp: 1".to_string(), &snapshot)
+            .await
+            .unwrap();
+        repository.write_head(&BranchName("fetched".to_string()), &fetched).await.unwrap();
+        let local_commits = repository.read_local_commits(&main()).await.unwrap();
+
+        client.reset("main".to_string(), "fetched".to_string(), None).await.unwrap();
+        assert_eq!(repository.read_head(&main()).await.unwrap(), Some(fetched));
+        assert_eq!(repository.read_local_commits(&main()).await.unwrap(), local_commits);
+    }
+
+    #[wasm_bindgen_test]
+    async fn modes() {
+        let client = tvc_client("reset_modes").await;
+        let first = commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        commit_files(&client, "main", &[("workspace/a.txt", "2"), ("workspace/b.txt", "2")]).await;
+        let head = client.repository().read_head(&main()).await.unwrap().unwrap();
+        write_files(&client, &[("workspace/a.txt", "3")]).await;
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+
+        client.reset("main".to_string(), first.0.0.clone(), Some(ResetMode::Soft)).await.unwrap();
+        assert!(!client.repository().read_staged_changes().await.unwrap().is_empty());
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"3".to_vec()));
+
+        client.reset("main".to_string(), head.0.0.clone(), Some(ResetMode::Mixed)).await.unwrap();
+        assert!(client.repository().read_staged_changes().await.unwrap().is_empty());
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"3".to_vec()));
+
+        client.reset("main".to_string(), first.0.0, Some(ResetMode::Hard)).await.unwrap();
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), None);
+    }
+
+    #[wasm_bindgen_test]
+    async fn restore_discards_working_changes() {
+        let client = tvc_client("reset_restore").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "1"), ("workspace/dir/b.txt", "1")]).await;
+        write_files(&client, &[
+            ("workspace/a.txt", "2"),
+            ("workspace/dir/b.txt", "2"),
+            ("workspace/dir/untracked.txt", "2"),
+        ]).await;
+
+        client.restore("workspace/dir".to_string(), None).await.unwrap();
+        assert_eq!(client.fs.read_file("workspace/dir/b.txt").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/dir/untracked.txt").await.unwrap(), Some(b"2".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"2".to_vec()));
+        assert!(client.restore("workspace/missing".to_string(), None).await.is_err());
+    }
+}
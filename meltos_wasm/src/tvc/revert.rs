@@ -0,0 +1,182 @@
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::object::commit::CommitHash;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{NOTHING_TO_COMMIT, TvcError};
+use crate::tvc::events::COMMITTED;
+use crate::tvc::merge::MergeResult;
+use crate::tvc::merge3::MergeStrategy;
+use crate::tvc::repository::Snapshot;
+use crate::tvc::signature::CommitInfo;
+use crate::tvc::WasmTvcClient;
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Creates a new commit on the checked out branch which undoes the changes of `commit`.
+    ///
+    /// The inverse of the changes is merged into the head, so conflicts are reported
+    /// and concluded in the same way as [`WasmTvcClient::merge`].
+    /// The changes of a merge commit are taken against its first parent.
+    pub async fn revert(&self, commit: String, strategy: Option<MergeStrategy>) -> error::Result<MergeResult> {
+        self.observe_heads(None, async {
+            let branch_name = self.checked_out_branch().await?;
+            let repository = self.repository();
+            let commit_hash = repository.resolve(&commit).await?;
+            let commit_obj = repository.read_commit(&commit_hash).await?;
+            let message = format!(
+                "Revert \"{}\"\n\nThis reverts commit {}.",
+                CommitInfo::parse(&commit_obj.text.0).message, commit_hash.0.0
+            );
+
+            let base = repository.read_snapshot(&commit_hash).await?;
+            let theirs = self.read_first_parent_snapshot(&commit_obj.parents).await?;
+            let result = self
+                .apply_commit_changes(&branch_name, base, theirs, message, None, strategy.unwrap_or_default())
+                .await?;
+            self.emit_merge_result(COMMITTED, &branch_name, &result);
+            Ok(result)
+        }).await
+    }
+
+    /// Creates a new commit on `onto_branch` which applies the changes of `commit`.
+    ///
+    /// The commit may be on any branch; conflicts are reported and concluded
+    /// in the same way as [`WasmTvcClient::merge`]. The author of `commit` is kept.
+    pub async fn cherry_pick(
+        &self,
+        commit: String,
+        onto_branch: String,
+        strategy: Option<MergeStrategy>,
+    ) -> error::Result<MergeResult> {
+        self.observe_heads(None, async {
+            let branch_name = BranchName(onto_branch);
+            let repository = self.repository();
+            let commit_hash = repository.resolve(&commit).await?;
+            let commit_obj = repository.read_commit(&commit_hash).await?;
+
+            let base = self.read_first_parent_snapshot(&commit_obj.parents).await?;
+            let theirs = repository.read_snapshot(&commit_hash).await?;
+            let mut info = CommitInfo::parse(&commit_obj.text.0);
+            let author = info.author.take();
+            info.timestamp = None;
+            let result = self
+                .apply_commit_changes(&branch_name, base, theirs, info.to_text(), author, strategy.unwrap_or_default())
+                .await?;
+            self.emit_merge_result(COMMITTED, &branch_name, &result);
+            Ok(result)
+        }).await
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Applies the changes from `base` to `theirs` onto the head of the branch as a new commit
+    /// made by `author`, or the current user if `None`.
+    async fn apply_commit_changes(
+        &self,
+        branch_name: &BranchName,
+        base: Snapshot,
+        theirs: Snapshot,
+        message: String,
+        author: Option<String>,
+        strategy: MergeStrategy,
+    ) -> error::Result<MergeResult> {
+        let repository = self.repository();
+        self.ensure_not_merging().await?;
+        let Some(head) = repository.read_head(branch_name).await? else {
+            return Err(TvcError::not_found("branch", &branch_name).into());
+        };
+        if self.is_checked_out(branch_name).await? {
+            self.ensure_no_changes(branch_name).await?;
+        }
+
+        let ours = repository.read_snapshot(&head).await?;
+        let merged = self.merge_snapshots(&base, &ours, &theirs, strategy).await?;
+        if merged.conflicts.is_empty() && merged.snapshot == ours {
+            return Err(TvcError::new(NOTHING_TO_COMMIT, "nothing to commit; the changes are already applied").into());
+        }
+        self.apply_merged_tree(branch_name, vec![head], message, author, &ours, merged).await
+    }
+
+
+    pub(crate) async fn read_first_parent_snapshot(&self, parents: &[CommitHash]) -> error::Result<Snapshot> {
+        match parents.first() {
+            Some(parent) => self.repository().read_snapshot(parent).await,
+            None => Ok(Snapshot::new()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use meltos_tvc::object::commit::CommitHash;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, diverged_client, tvc_client, write_files};
+    use crate::tvc::merge::{CONFLICTED, MERGED};
+    use crate::tvc::WasmTvcClient;
+
+    async fn head(client: &WasmTvcClient) -> CommitHash {
+        client.repository().read_head(&BranchName("main".to_string())).await.unwrap().unwrap()
+    }
+
+    #[wasm_bindgen_test]
+    async fn revert_cleanly() {
+        let client = tvc_client("revert_clean").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        let added = commit_files(&client, "main", &[("workspace/b.txt", "1")]).await;
+
+        let result = client.revert(added.0.0.clone(), None).await.unwrap();
+        assert_eq!(result.kind, MERGED);
+        let reverted = head(&client).await;
+        assert_eq!(result.commit, Some(reverted.0.0.clone()));
+        assert_eq!(client.repository().read_commit(&reverted).await.unwrap().parents, vec![added.clone()]);
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), None);
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"1".to_vec()));
+
+        assert!(client.revert(added.0.0, None).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn revert_conflicting() {
+        let client = tvc_client("revert_conflict").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "1\n")]).await;
+        let second = commit_files(&client, "main", &[("workspace/a.txt", "2\n")]).await;
+        let third = commit_files(&client, "main", &[("workspace/a.txt", "3\n")]).await;
+
+        let result = client.revert(second.0.0, None).await.unwrap();
+        assert_eq!(result.kind, CONFLICTED);
+        assert_eq!(result.conflicts[0].path, "workspace/a.txt");
+        assert_eq!(head(&client).await, third);
+        assert!(client.is_merging().await.unwrap());
+
+        write_files(&client, &[("workspace/a.txt", "1\n")]).await;
+        client.resolve_conflict("workspace/a.txt".to_string()).await.unwrap();
+        let result = client.conclude_merge(None).await.unwrap();
+        let reverted = head(&client).await;
+        assert_eq!(result.commit, Some(reverted.0.0.clone()));
+        assert_eq!(client.repository().read_commit(&reverted).await.unwrap().parents, vec![third]);
+    }
+
+    #[wasm_bindgen_test]
+    async fn cherry_pick_from_other_branch() {
+        let client = diverged_client(
+            "revert_cherry_pick",
+            &[("workspace/a.txt", "1")],
+            &[("workspace/a.txt", "2")],
+            &[("workspace/b.txt", "1")],
+        ).await;
+        let ours = head(&client).await;
+        let picked = client.repository().read_head(&BranchName("feature".to_string())).await.unwrap().unwrap();
+
+        let result = client.cherry_pick(picked.0.0, "main".to_string(), None).await.unwrap();
+        assert_eq!(result.kind, MERGED);
+        assert_eq!(client.repository().read_commit(&head(&client).await).await.unwrap().parents, vec![ours]);
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"2".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), Some(b"1".to_vec()));
+    }
+}
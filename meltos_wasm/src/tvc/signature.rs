@@ -0,0 +1,201 @@
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen_futures::js_sys::Date;
+
+use crate::tvc::WasmTvcClient;
+
+const AUTHOR: &str = "Author: ";
+const CO_AUTHOR: &str = "Co-authored-by: ";
+const TIMESTAMP: &str = "Timestamp: ";
+
+
+/// The message of a commit with who made it and when.
+///
+/// They are written as trailers at the end of the commit text,
+/// so that they are pushed and fetched along with the commit.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct CommitInfo {
+    pub message: String,
+    pub author: Option<String>,
+    pub co_authors: Vec<String>,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: Option<u64>,
+}
+
+
+impl CommitInfo {
+    pub fn parse(text: &str) -> Self {
+        let Some((message, trailers)) = text.rsplit_once("\n\n").filter(|(_, trailers)| is_trailers(trailers)) else {
+            return Self {
+                message: text.to_string(),
+                ..Self::default()
+            };
+        };
+
+        let mut info = Self {
+            message: message.to_string(),
+            ..Self::default()
+        };
+        for line in trailers.lines() {
+            if let Some(author) = line.strip_prefix(AUTHOR) {
+                info.author = Some(author.to_string());
+            } else if let Some(co_author) = line.strip_prefix(CO_AUTHOR) {
+                info.co_authors.push(co_author.to_string());
+            } else if let Some(timestamp) = line.strip_prefix(TIMESTAMP) {
+                info.timestamp = timestamp.parse().ok();
+            }
+        }
+        info
+    }
+
+
+    pub fn to_text(&self) -> String {
+        let mut trailers = Vec::new();
+        if let Some(author) = self.author.as_ref() {
+            trailers.push(format!("{AUTHOR}{author}"));
+        }
+        for co_author in &self.co_authors {
+            trailers.push(format!("{CO_AUTHOR}{co_author}"));
+        }
+        if let Some(timestamp) = self.timestamp {
+            trailers.push(format!("{TIMESTAMP}{timestamp}"));
+        }
+
+        if trailers.is_empty() {
+            self.message.clone()
+        } else {
+            format!("{}\n\n{}", self.message, trailers.join("\n"))
+        }
+    }
+
+
+    /// Replaces the message keeping who made the commit and when.
+    #[inline]
+    pub fn with_message(mut self, message: String) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Sets the user recorded as the author of commits.
+    ///
+    /// It is set to the user of the session by `open_room` and `join_room`,
+    /// so this is needed only when reusing a session saved elsewhere.
+    #[inline(always)]
+    pub fn set_user_id(&mut self, user_id: Option<String>) {
+        self.user_id = user_id;
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Writes the current user and time into the commit text.
+    #[inline]
+    pub(crate) fn sign(&self, text: &str, co_authors: Vec<String>) -> String {
+        self.sign_as(text, None, co_authors)
+    }
+
+
+    /// Writes `author`, or the current user if `None`, and the current time into the commit text.
+    ///
+    /// An author and a timestamp already written in `text` are replaced,
+    /// so that nobody can commit as someone else; `author` is given only by operations
+    /// which rewrite a commit of someone else, such as cherry-pick.
+    pub(crate) fn sign_as(&self, text: &str, author: Option<String>, co_authors: Vec<String>) -> String {
+        let mut info = CommitInfo::parse(text);
+        info.author = author.or_else(|| self.user_id.clone());
+        for co_author in co_authors {
+            if !info.co_authors.contains(&co_author) {
+                info.co_authors.push(co_author);
+            }
+        }
+        info.timestamp = Some(Date::now() as u64);
+        info.to_text()
+    }
+}
+
+
+#[inline]
+fn is_trailers(text: &str) -> bool {
+    !text.is_empty() && text
+        .lines()
+        .all(|line| [AUTHOR, CO_AUTHOR, TIMESTAMP].iter().any(|key| line.starts_with(key)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{diverged_client, wasm_fs};
+    use crate::tvc::signature::CommitInfo;
+    use crate::tvc::WasmTvcClient;
+
+    #[wasm_bindgen_test]
+    fn parse_trailers() {
+        let info = CommitInfo::parse("fix bug\n\ndetails\n\nAuthor: alice\nCo-authored-by: bob\nTimestamp: 1700000000000");
+        assert_eq!(info, CommitInfo {
+            message: "fix bug\n\ndetails".to_string(),
+            author: Some("alice".to_string()),
+            co_authors: vec!["bob".to_string()],
+            timestamp: Some(1700000000000),
+        });
+    }
+
+    #[wasm_bindgen_test]
+    fn message_without_trailers() {
+        let info = CommitInfo::parse("fix bug\n\ndetails");
+        assert_eq!(info.message, "fix bug\n\ndetails");
+        assert_eq!(info.author, None);
+        assert_eq!(info.to_text(), "fix bug\n\ndetails");
+    }
+
+    #[wasm_bindgen_test]
+    fn round_trip() {
+        let info = CommitInfo {
+            message: "commit".to_string(),
+            author: Some("alice".to_string()),
+            co_authors: Vec::new(),
+            timestamp: Some(1),
+        };
+        assert_eq!(CommitInfo::parse(&info.to_text()), info);
+    }
+
+    #[wasm_bindgen_test]
+    async fn sign_as_session_user() {
+        let mut client = WasmTvcClient::new(&wasm_fs("signature_user").await);
+        client.set_user_id(Some("alice".to_string()));
+
+        let info = CommitInfo::parse(&client.sign("commit\n\nAuthor: mallory\nTimestamp: 1", vec!["bob".to_string()]));
+        assert_eq!(info.message, "commit");
+        assert_eq!(info.author.as_deref(), Some("alice"));
+        assert_eq!(info.co_authors, vec!["bob".to_string()]);
+        assert_ne!(info.timestamp, Some(1));
+
+        let info = CommitInfo::parse(&client.sign_as("commit\n\nAuthor: mallory", Some("carol".to_string()), Vec::new()));
+        assert_eq!(info.author.as_deref(), Some("carol"));
+    }
+
+    #[wasm_bindgen_test]
+    async fn cherry_pick_keeps_author() {
+        let mut client = diverged_client(
+            "signature_cherry_pick",
+            &[("workspace/a.txt", "a")],
+            &[("workspace/b.txt", "b")],
+            &[("workspace/c.txt", "c")],
+        ).await;
+        let repository = client.repository();
+        let picked = repository.read_head(&BranchName("feature".to_string())).await.unwrap().unwrap();
+        let picked_info = CommitInfo::parse(&repository.read_commit(&picked).await.unwrap().text.0);
+        client.set_user_id(Some("someone else".to_string()));
+
+        client.cherry_pick(picked.0.0, "main".to_string(), None).await.unwrap();
+        let head = repository.read_head(&BranchName("main".to_string())).await.unwrap().unwrap();
+        let info = CommitInfo::parse(&repository.read_commit(&head).await.unwrap().text.0);
+        assert_eq!(info.message, picked_info.message);
+        assert_eq!(info.author, picked_info.author);
+    }
+}
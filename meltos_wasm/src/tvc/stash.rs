@@ -0,0 +1,352 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use meltos_tvc::object::commit::CommitHash;
+use meltos_tvc::object::ObjHash;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{classify, CORRUPTED, IntoJsResult, INVALID_OPERATION, NOTHING_TO_COMMIT, TvcError};
+use crate::js_vec::JsVecStashEntry;
+use crate::tvc::merge::{CONFLICTED, MERGED, MergeResult};
+use crate::tvc::merge3::MergeStrategy;
+use crate::tvc::status::{STAGED_ADDED, STAGED_DELETED, STAGED_MODIFIED, UNTRACKED};
+use crate::tvc::repository::Snapshot;
+use crate::tvc::WasmTvcClient;
+use crate::tvc::worktree::is_under;
+
+const STASH: &str = "STASH";
+
+
+/// A shelved set of changes.
+///
+/// The working files are saved as a commit object whose parents are the head at the time
+/// and a commit of the staged files, so that they survive restarts;
+/// the list of stashes is saved separately.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StashEntry {
+    pub commit: String,
+    pub branch: String,
+    pub message: String,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Shelves the changes of the tracked files in the checked out branch
+    /// and restores them to the head.
+    ///
+    /// If `paths` is given, only the files under them are shelved.
+    pub async fn stash_push(&self, message: Option<String>, paths: Option<Vec<String>>) -> error::Result<StashEntry> {
+        let branch_name = self.checked_out_branch().await?;
+        let repository = self.repository();
+        let Some(head) = repository.read_head(&branch_name).await? else {
+            return Err(TvcError::new(INVALID_OPERATION, format!("branch has no commits: {branch_name}")).into());
+        };
+        let head_snapshot = repository.read_snapshot(&head).await?;
+
+        let in_scope = |path: &str| paths
+            .as_ref()
+            .is_none_or(|paths| paths.iter().any(|scope| is_under(path, scope)));
+        // A file both staged and modified afterwards has two entries.
+        let mut changed = BTreeMap::<String, bool>::new();
+        for entry in self.status_entries(&branch_name).await? {
+            if entry.status == UNTRACKED || !in_scope(&entry.path) {
+                continue;
+            }
+            let staged = [STAGED_ADDED, STAGED_MODIFIED, STAGED_DELETED].contains(&entry.status.as_str());
+            *changed.entry(entry.path).or_default() |= staged;
+        }
+        if changed.is_empty() {
+            return Err(TvcError::new(NOTHING_TO_COMMIT, "no local changes to stash").into());
+        }
+
+        let mut index = head_snapshot.clone();
+        for (path, obj_hash) in repository.read_staged_changes().await? {
+            if changed.get(&path) != Some(&true) {
+                continue;
+            }
+            match obj_hash {
+                Some(obj_hash) => index.insert(path, obj_hash),
+                None => index.remove(&path),
+            };
+        }
+
+        let mut stashed = head_snapshot.clone();
+        for (path, staged) in &changed {
+            match self.fs.read_file(path).await.into_js_result()? {
+                Some(buf) => stashed.insert(path.clone(), repository.write_file_obj(buf).await?),
+                None => stashed.remove(path),
+            };
+            if *staged {
+                self.tvc.un_stage(path).await.map_err(classify)?;
+            }
+        }
+
+        let message = message.unwrap_or_else(|| format!("WIP on {branch_name}"));
+        let index_text = self.sign(&format!("index on {branch_name}"), Vec::new());
+        let index_hash = repository.write_detached_commit(vec![head.clone()], index_text, &index).await?;
+        let parents = vec![head, index_hash];
+        let commit_hash = repository.write_detached_commit(parents, self.sign(&message, Vec::new()), &stashed).await?;
+        self.write_working_files(&stashed, &head_snapshot).await?;
+
+        let entry = StashEntry {
+            commit: commit_hash.0.0,
+            branch: branch_name.0,
+            message,
+        };
+        let mut stashes = self.read_stashes().await?;
+        stashes.insert(0, entry.clone());
+        repository.write_state(STASH, &stashes).await?;
+        Ok(entry)
+    }
+
+    /// Lists the stashes, the newest first.
+    #[inline(always)]
+    pub async fn stash_list(&self) -> error::Result<JsVecStashEntry> {
+        Ok(JsVecStashEntry(self.read_stashes().await?))
+    }
+
+    /// Reapplies the stash at `index` onto the working files of the checked out branch.
+    ///
+    /// The stash is merged with the head as ours and the head at the time of stashing as the base,
+    /// so changes to the same lines are written with conflict markers.
+    /// The files which were staged are staged again unless they conflict or the head has changed them.
+    pub async fn stash_apply(&self, index: Option<usize>) -> error::Result<MergeResult> {
+        let entry = self.read_stash(index.unwrap_or(0)).await?;
+        let branch_name = self.checked_out_branch().await?;
+        self.ensure_no_changes(&branch_name).await?;
+
+        let repository = self.repository();
+        let stash_hash = CommitHash(ObjHash(entry.commit));
+        let stash = repository.read_commit(&stash_hash).await?;
+        let Some(base_hash) = stash.parents.first() else {
+            return Err(TvcError::new(CORRUPTED, format!("stash has no parent commit: {}", stash_hash.0.0)).into());
+        };
+        let base = repository.read_snapshot(base_hash).await?;
+        let stashed = repository.read_snapshot(&stash_hash).await?;
+        let ours = match repository.read_head(&branch_name).await? {
+            Some(head) => repository.read_snapshot(&head).await?,
+            None => Default::default(),
+        };
+
+        let merged = self.merge_snapshots(&base, &ours, &stashed, MergeStrategy::Normal).await?;
+        let conflicts = self.write_merged_working_files(&ours, &merged).await?;
+        if let Some(index_hash) = stash.parents.get(1) {
+            let index = repository.read_snapshot(index_hash).await?;
+            let conflicted: BTreeSet<&String> = conflicts.iter().map(|conflict| &conflict.path).collect();
+            self.restore_staged(&base, &ours, &index, &conflicted).await?;
+        }
+        Ok(MergeResult {
+            kind: if conflicts.is_empty() { MERGED } else { CONFLICTED }.to_string(),
+            commit: None,
+            conflicts,
+        })
+    }
+
+    /// Applies the stash at `index` and drops it if it is applied without conflicts.
+    pub async fn stash_pop(&self, index: Option<usize>) -> error::Result<MergeResult> {
+        let index = index.unwrap_or(0);
+        let result = self.stash_apply(Some(index)).await?;
+        if result.conflicts.is_empty() {
+            self.stash_drop(Some(index)).await?;
+        }
+        Ok(result)
+    }
+
+    pub async fn stash_drop(&self, index: Option<usize>) -> error::Result {
+        let index = index.unwrap_or(0);
+        let mut stashes = self.read_stashes().await?;
+        if stashes.len() <= index {
+            return Err(TvcError::not_found("stash", index).into());
+        }
+        stashes.remove(index);
+        self.repository().write_state(STASH, &stashes).await
+    }
+}
+
+
+impl WasmTvcClient {
+    pub(crate) async fn checked_out_branch(&self) -> error::Result<BranchName> {
+        self
+            .repository()
+            .read_working_branch()
+            .await?
+            .ok_or_else(|| TvcError::new(INVALID_OPERATION, "no branch is checked out").into())
+    }
+
+
+    /// Stages the files changed from `base` in `index`,
+    /// except the conflicted ones and the ones changed in `ours` since `base`.
+    async fn restore_staged(
+        &self,
+        base: &Snapshot,
+        ours: &Snapshot,
+        index: &Snapshot,
+        conflicted: &BTreeSet<&String>,
+    ) -> error::Result {
+        let repository = self.repository();
+        let paths: BTreeSet<&String> = base.keys().chain(index.keys()).collect();
+        for path in paths {
+            let staged = index.get(path);
+            if staged == base.get(path) || ours.get(path) != base.get(path) || conflicted.contains(path) {
+                continue;
+            }
+            match (staged, base.get(path)) {
+                (Some(obj_hash), _) => repository.write_staged_obj(path, Some(obj_hash.clone())).await?,
+                (None, Some(obj_hash)) => repository.write_staged_deletion(path, obj_hash).await?,
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+
+    #[inline]
+    async fn read_stashes(&self) -> error::Result<Vec<StashEntry>> {
+        Ok(self.repository().read_state(STASH).await?.unwrap_or_default())
+    }
+
+
+    async fn read_stash(&self, index: usize) -> error::Result<StashEntry> {
+        self
+            .read_stashes()
+            .await?
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| TvcError::not_found("stash", index).into())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, tvc_client, write_files};
+    use crate::tvc::merge::{CONFLICTED, MERGED};
+    use crate::tvc::repository::Snapshot;
+    use crate::tvc::stash::{STASH, StashEntry};
+    use crate::tvc::status::{MODIFIED, STAGED_DELETED, STAGED_MODIFIED};
+    use crate::tvc::WasmTvcClient;
+
+    async fn statuses(client: &WasmTvcClient) -> Vec<(String, String)> {
+        client
+            .status_entries(&BranchName("main".to_string()))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path, entry.status))
+            .collect()
+    }
+
+    async fn read(client: &WasmTvcClient, path: &str) -> Option<Vec<u8>> {
+        client.fs.read_file(path).await.unwrap()
+    }
+
+    /// Commits `a.txt`, `b.txt` and `c.txt`, then stages `a.txt` as `staged` and modifies it into `working`,
+    /// modifies `b.txt` without staging it and stages the deletion of `c.txt`.
+    async fn changed_client(name: &str) -> WasmTvcClient {
+        let client = tvc_client(name).await;
+        commit_files(&client, "main", &[
+            ("workspace/a.txt", "base\n"),
+            ("workspace/b.txt", "base\n"),
+            ("workspace/c.txt", "base\n"),
+        ]).await;
+        write_files(&client, &[("workspace/a.txt", "staged\n")]).await;
+        client.fs.delete("workspace/c.txt").await.unwrap();
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+        write_files(&client, &[("workspace/a.txt", "working\n"), ("workspace/b.txt", "working\n")]).await;
+        client
+    }
+
+    fn changed_statuses() -> Vec<(String, String)> {
+        [
+            ("workspace/a.txt", STAGED_MODIFIED),
+            ("workspace/a.txt", MODIFIED),
+            ("workspace/b.txt", MODIFIED),
+            ("workspace/c.txt", STAGED_DELETED),
+        ]
+            .into_iter()
+            .map(|(path, status)| (path.to_string(), status.to_string()))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    async fn push_and_pop_keep_staged_and_working_changes() {
+        let client = changed_client("stash_pop").await;
+
+        let entry = client.stash_push(None, None).await.unwrap();
+        assert_eq!(entry.message, "WIP on main");
+        assert_eq!(statuses(&client).await, Vec::new());
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"base\n".to_vec()));
+        assert_eq!(read(&client, "workspace/c.txt").await, Some(b"base\n".to_vec()));
+        assert_eq!(client.stash_list().await.unwrap().0, vec![entry]);
+
+        let result = client.stash_pop(None).await.unwrap();
+        assert_eq!(result.kind, MERGED);
+        assert_eq!(statuses(&client).await, changed_statuses());
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"working\n".to_vec()));
+        let staged = client.repository().read_staged_changes().await.unwrap();
+        let staged_a = client.repository().read_file_obj(staged["workspace/a.txt"].as_ref().unwrap()).await.unwrap();
+        assert_eq!(staged_a, b"staged\n".to_vec());
+        assert_eq!(client.stash_list().await.unwrap().0, Vec::new());
+    }
+
+    #[wasm_bindgen_test]
+    async fn push_only_paths() {
+        let client = changed_client("stash_paths").await;
+
+        client.stash_push(Some("b".to_string()), Some(vec!["workspace/b.txt".to_string()])).await.unwrap();
+        assert_eq!(read(&client, "workspace/a.txt").await, Some(b"working\n".to_vec()));
+        assert_eq!(read(&client, "workspace/b.txt").await, Some(b"base\n".to_vec()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn apply_keeps_stash() {
+        let client = changed_client("stash_apply").await;
+        client.stash_push(None, None).await.unwrap();
+
+        client.stash_apply(None).await.unwrap();
+        assert_eq!(statuses(&client).await, changed_statuses());
+        assert_eq!(client.stash_list().await.unwrap().0.len(), 1);
+        assert!(client.stash_apply(Some(1)).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn pop_keeps_conflicted_stash() {
+        let client = tvc_client("stash_conflict").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "base\n")]).await;
+        write_files(&client, &[("workspace/a.txt", "stashed\n")]).await;
+        client.stash_push(None, None).await.unwrap();
+        commit_files(&client, "main", &[("workspace/a.txt", "committed\n")]).await;
+
+        let result = client.stash_pop(None).await.unwrap();
+        assert_eq!(result.kind, CONFLICTED);
+        assert_eq!(result.conflicts[0].path, "workspace/a.txt");
+        assert_eq!(client.stash_list().await.unwrap().0.len(), 1);
+    }
+
+    #[wasm_bindgen_test]
+    async fn reject_stash_without_parent() {
+        let client = tvc_client("stash_malformed").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "base\n")]).await;
+        let repository = client.repository();
+        let commit = repository
+            .write_detached_commit(Vec::new(), "stash".to_string(), &Snapshot::new())
+            .await
+            .unwrap();
+        repository.write_state(STASH, &vec![StashEntry {
+            commit: commit.0.0,
+            branch: "main".to_string(),
+            message: "stash".to_string(),
+        }]).await.unwrap();
+
+        assert!(client.stash_apply(None).await.is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::BTreeSet;
+
+use meltos_tvc::branch::BranchName;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::TvcError;
+use crate::js_vec::JsVecStatusEntry;
+use crate::tvc::WasmTvcClient;
+use crate::tvc::worktree::Tree;
+
+pub const UNTRACKED: &str = "untracked";
+pub const MODIFIED: &str = "modified";
+pub const DELETED: &str = "deleted";
+pub const STAGED_ADDED: &str = "staged-added";
+pub const STAGED_MODIFIED: &str = "staged-modified";
+pub const STAGED_DELETED: &str = "staged-deleted";
+pub const CONFLICTED: &str = "conflicted";
+
+
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StatusEntry {
+    pub path: String,
+    /// One of `untracked`, `modified`, `deleted`,
+    /// `staged-added`, `staged-modified`, `staged-deleted` or `conflicted`.
+    pub status: String,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Lists the staged changes against the head of the branch
+    /// and the working changes against the staging area.
+    ///
+    /// A file both staged and modified afterwards has an entry for each.
+    /// A file left conflicted by a paused merge is `conflicted` until it is marked resolved.
+    pub async fn status(&self, branch_name: String) -> error::Result<JsVecStatusEntry> {
+        let entries = self.status_entries(&BranchName(branch_name)).await?;
+        Ok(JsVecStatusEntry(entries))
+    }
+}
+
+
+impl WasmTvcClient {
+    pub(crate) async fn status_entries(&self, branch_name: &BranchName) -> error::Result<Vec<StatusEntry>> {
+        let head = self.head_tree(branch_name).await?;
+        let staged = self.staged_tree(branch_name).await?;
+        let working = self.working_tree().await?;
+
+        let mut entries = Vec::new();
+        self.compare(&head, &staged, [STAGED_ADDED, STAGED_MODIFIED, STAGED_DELETED], &mut entries).await?;
+        self.compare(&staged, &working, [UNTRACKED, MODIFIED, DELETED], &mut entries).await?;
+
+        let conflicted = self.unresolved_conflicts().await?;
+        entries.retain(|entry| !conflicted.contains(&entry.path));
+        entries.extend(conflicted.into_iter().map(|path| StatusEntry {
+            path,
+            status: CONFLICTED.to_string(),
+        }));
+        entries.sort_by(|lhs, rhs| lhs.path.cmp(&rhs.path));
+        Ok(entries)
+    }
+
+
+    /// Fails if there are staged changes or modified tracked files on the branch.
+    pub(crate) async fn ensure_no_changes(&self, branch_name: &BranchName) -> error::Result {
+        let changed: Vec<String> = self
+            .status_entries(branch_name)
+            .await?
+            .into_iter()
+            .filter(|entry| entry.status != UNTRACKED)
+            .map(|entry| entry.path)
+            .collect();
+        if changed.is_empty() {
+            Ok(())
+        } else {
+            Err(TvcError::conflict(format!("there are uncommitted changes: {}", changed.join(", ")), changed).into())
+        }
+    }
+
+
+    /// Pushes an entry for each file added, modified or deleted from `old` to `new`,
+    /// labelled with the corresponding status of `[added, modified, deleted]`.
+    async fn compare(
+        &self,
+        old: &Tree,
+        new: &Tree,
+        [added, modified, deleted]: [&str; 3],
+        entries: &mut Vec<StatusEntry>,
+    ) -> error::Result {
+        let paths: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for path in paths {
+            let status = match (old.get(path), new.get(path)) {
+                (Some(old_blob), Some(new_blob)) => {
+                    if self.is_same_blob(old_blob, new_blob).await? {
+                        continue;
+                    }
+                    modified
+                }
+                (None, Some(_)) => added,
+                (Some(_), None) => deleted,
+                (None, None) => continue,
+            };
+            entries.push(StatusEntry {
+                path: path.clone(),
+                status: status.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::branch::BranchName;
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, diverged_client, tvc_client, write_files};
+    use crate::tvc::status::{
+        CONFLICTED, DELETED, MODIFIED, STAGED_ADDED, STAGED_DELETED, STAGED_MODIFIED, UNTRACKED,
+    };
+    use crate::tvc::WasmTvcClient;
+
+    async fn statuses(client: &WasmTvcClient) -> Vec<(String, String)> {
+        client
+            .status_entries(&BranchName("main".to_string()))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path, entry.status))
+            .collect()
+    }
+
+    fn expected(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(path, status)| (path.to_string(), status.to_string()))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    async fn classify_staged_and_working_changes() {
+        let client = tvc_client("status_classify").await;
+        commit_files(&client, "main", &[
+            ("workspace/modified.txt", "1"),
+            ("workspace/deleted.txt", "1"),
+            ("workspace/staged.txt", "1"),
+            ("workspace/staged_deleted.txt", "1"),
+        ]).await;
+
+        write_files(&client, &[("workspace/staged.txt", "2"), ("workspace/added.txt", "1")]).await;
+        client.fs.delete("workspace/staged_deleted.txt").await.unwrap();
+        client.stage("main".to_string(), "workspace".to_string()).await.unwrap();
+        write_files(&client, &[
+            ("workspace/modified.txt", "2"),
+            ("workspace/added.txt", "2"),
+            ("workspace/untracked.txt", "1"),
+        ]).await;
+        client.fs.delete("workspace/deleted.txt").await.unwrap();
+
+        assert_eq!(statuses(&client).await, expected(&[
+            ("workspace/added.txt", STAGED_ADDED),
+            ("workspace/added.txt", MODIFIED),
+            ("workspace/deleted.txt", DELETED),
+            ("workspace/modified.txt", MODIFIED),
+            ("workspace/staged.txt", STAGED_MODIFIED),
+            ("workspace/staged_deleted.txt", STAGED_DELETED),
+            ("workspace/untracked.txt", UNTRACKED),
+        ]));
+    }
+
+    #[wasm_bindgen_test]
+    async fn clean_after_commit() {
+        let client = tvc_client("status_clean").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        assert_eq!(statuses(&client).await, Vec::new());
+    }
+
+    #[wasm_bindgen_test]
+    async fn conflicted_until_resolved() {
+        let client = diverged_client(
+            "status_conflicted",
+            &[("workspace/a.txt", "base\n")],
+            &[("workspace/a.txt", "ours\n")],
+            &[("workspace/a.txt", "theirs\n")],
+        ).await;
+
+        client.merge("main".to_string(), "feature".to_string(), None, None, None).await.unwrap();
+        assert_eq!(statuses(&client).await, expected(&[("workspace/a.txt", CONFLICTED)]));
+
+        client.resolve_conflict("workspace/a.txt".to_string()).await.unwrap();
+        assert_eq!(statuses(&client).await, expected(&[("workspace/a.txt", MODIFIED)]));
+    }
+}
@@ -0,0 +1,207 @@
+use meltos_tvc::branch::BranchName;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::error;
+use crate::error::{CORRUPTED, INVALID_OPERATION, TvcError};
+use crate::js_vec::JsVecTag;
+use crate::tvc::branch::validate_ref_name;
+use crate::tvc::repository::{looks_like_hash, Snapshot};
+use crate::tvc::WasmTvcClient;
+
+/// The branch whose head commit has a file for each tag, so that `push` and `fetch` transfer the tags
+/// in the bundle with the commits.
+///
+/// It starts with `.`, which a branch name given by the user can't, and it isn't listed as a branch.
+pub(crate) const TAGS_BRANCH: &str = ".tags";
+
+
+/// A name fixed to a commit, such as a release point.
+///
+/// `push` sends the tags and `fetch` adds the tags which the room has;
+/// a deleted tag is deleted only in the local repository.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+    pub commit: String,
+    pub message: Option<String>,
+}
+
+
+#[wasm_bindgen]
+impl WasmTvcClient {
+    /// Tags `commit`, a branch name, a tag name or a commit hash.
+    ///
+    /// A name which looks like a commit hash is refused, since it would be resolved as the commit.
+    pub async fn create_tag(&self, name: String, commit: String, message: Option<String>) -> error::Result<Tag> {
+        validate_ref_name("tag", &name)?;
+        if looks_like_hash(&name) {
+            return Err(TvcError::new(INVALID_OPERATION, format!("tag name looks like a commit hash: {name}")).into());
+        }
+        let repository = self.repository();
+        if repository.read_tag(&name).await?.is_some() {
+            return Err(TvcError::new(INVALID_OPERATION, format!("tag already exists: {name}")).into());
+        }
+
+        let commit_hash = repository.resolve(&commit).await?;
+        let tag = Tag {
+            name,
+            commit: commit_hash.0.0,
+            message,
+        };
+        repository.write_tag(&tag).await?;
+        Ok(tag)
+    }
+
+    pub async fn delete_tag(&self, name: String) -> error::Result {
+        let repository = self.repository();
+        if repository.read_tag(&name).await?.is_none() {
+            return Err(TvcError::not_found("tag", &name).into());
+        }
+        repository.delete_tag(&name).await
+    }
+
+    /// Lists the tags sorted by their name.
+    #[inline(always)]
+    pub async fn tags(&self) -> error::Result<JsVecTag> {
+        Ok(JsVecTag(self.repository().read_tags().await?))
+    }
+}
+
+
+impl WasmTvcClient {
+    /// Commits the tags onto [`TAGS_BRANCH`] if they have changed since the last time,
+    /// so that the next `push` sends them.
+    pub(crate) async fn record_tags(&self) -> error::Result {
+        let repository = self.repository();
+        let branch_name = BranchName(TAGS_BRANCH.to_string());
+        let mut snapshot = Snapshot::new();
+        for tag in repository.read_tags().await? {
+            let buf = serde_json::to_vec(&tag).map_err(|e| TvcError::new(CORRUPTED, e.to_string()))?;
+            snapshot.insert(tag.name, repository.write_file_obj(buf).await?);
+        }
+
+        let head = repository.read_head(&branch_name).await?;
+        let recorded = match head.as_ref() {
+            Some(head) => repository.read_snapshot(head).await?,
+            None => Snapshot::new(),
+        };
+        if recorded == snapshot {
+            return Ok(());
+        }
+        repository
+            .write_commit(&branch_name, head.into_iter().collect(), "tags".to_string(), &snapshot)
+            .await?;
+        Ok(())
+    }
+
+
+    /// Adds the tags recorded at the head of [`TAGS_BRANCH`] which the local repository doesn't have,
+    /// such as the tags received by `fetch`.
+    pub(crate) async fn apply_received_tags(&self) -> error::Result {
+        let repository = self.repository();
+        let Some(head) = repository.read_head(&BranchName(TAGS_BRANCH.to_string())).await? else {
+            return Ok(());
+        };
+        for (name, obj_hash) in repository.read_snapshot(&head).await? {
+            if repository.read_tag(&name).await?.is_some() {
+                continue;
+            }
+            let buf = repository.read_file_obj(&obj_hash).await?;
+            let tag: Tag = serde_json::from_slice(&buf).map_err(|e| TvcError::new(CORRUPTED, e.to_string()))?;
+            repository.write_tag(&tag).await?;
+        }
+        Ok(())
+    }
+}
+
+
+#[inline]
+pub(crate) fn is_tags_branch(branch_name: &BranchName) -> bool {
+    branch_name.0 == TAGS_BRANCH
+}
+
+
+#[cfg(test)]
+mod tests {
+    use meltos_tvc::file_system::FileSystem;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::tests::{commit_files, tvc_client, wasm_fs, write_files};
+    use crate::tvc::tag::{Tag, TAGS_BRANCH};
+    use crate::tvc::WasmTvcClient;
+
+    #[wasm_bindgen_test]
+    async fn create_list_and_delete() {
+        let client = tvc_client("tag_crud").await;
+        let head = commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+
+        let tag = client.create_tag("v1".to_string(), "main".to_string(), Some("release".to_string())).await.unwrap();
+        assert_eq!(tag, Tag {
+            name: "v1".to_string(),
+            commit: head.0.0.clone(),
+            message: Some("release".to_string()),
+        });
+        client.create_tag("v0".to_string(), head.0.0.clone(), None).await.unwrap();
+        let names: Vec<String> = client.tags().await.unwrap().0.into_iter().map(|tag| tag.name).collect();
+        assert_eq!(names, vec!["v0", "v1"]);
+
+        assert!(client.create_tag("v1".to_string(), "main".to_string(), None).await.is_err());
+        assert!(client.create_tag("a b".to_string(), "main".to_string(), None).await.is_err());
+        assert!(client.create_tag(head.0.0.clone(), "main".to_string(), None).await.is_err());
+
+        client.delete_tag("v0".to_string()).await.unwrap();
+        assert_eq!(client.tags().await.unwrap().0, vec![tag]);
+        assert!(client.delete_tag("v0".to_string()).await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn resolve_tags_and_commits() {
+        let client = tvc_client("tag_resolve").await;
+        let first = commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        let second = commit_files(&client, "main", &[("workspace/a.txt", "2")]).await;
+        client.create_tag("v1".to_string(), first.0.0.clone(), None).await.unwrap();
+
+        let repository = client.repository();
+        assert_eq!(repository.resolve("v1").await.unwrap(), first);
+        assert_eq!(repository.resolve(&second.0.0).await.unwrap(), second);
+        assert_eq!(repository.resolve("main").await.unwrap(), second);
+        assert!(repository.resolve("missing").await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn unzip_tag_deletes_stale_files() {
+        let client = tvc_client("tag_unzip").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "1")]).await;
+        client.create_tag("v1".to_string(), "main".to_string(), None).await.unwrap();
+        commit_files(&client, "main", &[("workspace/a.txt", "2"), ("workspace/b.txt", "2")]).await;
+        write_files(&client, &[("workspace/untracked.txt", "1")]).await;
+
+        client.unzip("v1".to_string(), None, None).await.unwrap();
+        assert_eq!(client.fs.read_file("workspace/a.txt").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(client.fs.read_file("workspace/b.txt").await.unwrap(), None);
+        assert_eq!(client.fs.read_file("workspace/untracked.txt").await.unwrap(), Some(b"1".to_vec()));
+    }
+
+    #[wasm_bindgen_test]
+    async fn transfer_tags_with_the_commits() {
+        let client = tvc_client("tag_send").await;
+        commit_files(&client, "main", &[("workspace/a.txt", "a")]).await;
+        let tag = client.create_tag("v1".to_string(), "main".to_string(), Some("release".to_string())).await.unwrap();
+
+        // `push` records the tags before sending the local commits, which `fetch` receives as a bundle.
+        client.record_tags().await.unwrap();
+        let bundle = client
+            .export_bundle(vec!["main".to_string(), TAGS_BRANCH.to_string()], None, None, None)
+            .await
+            .unwrap();
+        let names: Vec<String> = client.branch_names().await.unwrap().0;
+        assert_eq!(names, vec!["main"]);
+
+        let receiver = WasmTvcClient::new(&wasm_fs("tag_receive").await);
+        receiver.import_bundle(bundle.0, None).await.unwrap();
+        assert_eq!(receiver.tags().await.unwrap().0, vec![tag.clone()]);
+        assert_eq!(receiver.repository().resolve("v1").await.unwrap().0.0, tag.commit);
+    }
+}
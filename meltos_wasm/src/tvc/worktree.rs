@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use meltos_tvc::branch::BranchName;
+use meltos_tvc::file_system::FileSystem;
+use meltos_tvc::object::ObjHash;
+
+use crate::error;
+use crate::error::{IntoJsResult, TvcError};
+use crate::progress::{PhaseProgress, WRITING_FILES};
+use crate::tvc::repository::Snapshot;
+use crate::tvc::WasmTvcClient;
+
+pub const WORKSPACE: &str = "workspace";
+
+
+/// Where the content of a file is read from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Blob {
+    /// A file object stored in the repository.
+    Obj(ObjHash),
+    /// A working file in the workspace.
+    Working(String),
+}
+
+
+/// The files of a working tree, a staging area or a commit keyed by their path.
+pub type Tree = BTreeMap<String, Blob>;
+
+
+impl WasmTvcClient {
+    pub(crate) async fn working_tree(&self) -> error::Result<Tree> {
+        Ok(self
+            .fs
+            .all_files_in(WORKSPACE)
+            .await
+            .into_js_result()?
+            .into_iter()
+            .map(|path| (path.clone(), Blob::Working(path)))
+            .collect())
+    }
+
+
+    /// Reads the files committed at the head of the branch.
+    ///
+    /// Returns an empty tree if the branch has no commits yet.
+    pub(crate) async fn head_tree(&self, branch_name: &BranchName) -> error::Result<Tree> {
+        let repository = self.repository();
+        match repository.read_head(branch_name).await? {
+            Some(head) => self.commit_tree(&head.0.0).await,
+            None => Ok(Tree::new()),
+        }
+    }
+
+
+    /// Reads the files committed at the branch or commit.
+    pub(crate) async fn commit_tree(&self, branch_or_commit: &str) -> error::Result<Tree> {
+        let repository = self.repository();
+        let commit_hash = repository.resolve(branch_or_commit).await?;
+        Ok(repository
+            .read_snapshot(&commit_hash)
+            .await?
+            .into_iter()
+            .map(|(path, obj_hash)| (path, Blob::Obj(obj_hash)))
+            .collect())
+    }
+
+
+    /// Reads the files committed at the head of the branch with the staged changes applied.
+    pub(crate) async fn staged_tree(&self, branch_name: &BranchName) -> error::Result<Tree> {
+        let mut tree = self.head_tree(branch_name).await?;
+        for (path, obj_hash) in self.repository().read_staged_changes().await? {
+            match obj_hash {
+                Some(obj_hash) => tree.insert(path, Blob::Obj(obj_hash)),
+                None => tree.remove(&path),
+            };
+        }
+        Ok(tree)
+    }
+
+
+    pub(crate) async fn read_blob(&self, blob: &Blob) -> error::Result<Vec<u8>> {
+        match blob {
+            Blob::Obj(obj_hash) => self.repository().read_file_obj(obj_hash).await,
+            Blob::Working(path) => self
+                .fs
+                .read_file(path)
+                .await
+                .into_js_result()?
+                .ok_or_else(|| TvcError::not_found("file", &path).into()),
+        }
+    }
+
+
+    pub(crate) async fn is_same_blob(&self, lhs: &Blob, rhs: &Blob) -> error::Result<bool> {
+        if let (Blob::Obj(lhs), Blob::Obj(rhs)) = (lhs, rhs) {
+            if lhs == rhs {
+                return Ok(true);
+            }
+        }
+        Ok(self.read_blob(lhs).await? == self.read_blob(rhs).await?)
+    }
+
+
+    /// Rewrites the working files committed in `from` into the files committed in `to`.
+    pub(crate) async fn write_working_files(&self, from: &Snapshot, to: &Snapshot) -> error::Result {
+        let deleted: Vec<&String> = from.keys().filter(|path| !to.contains_key(*path)).collect();
+        let written: Vec<(&String, &ObjHash)> = to.iter().filter(|(path, obj_hash)| from.get(*path) != Some(*obj_hash)).collect();
+        let mut progress = PhaseProgress::new(self.progress.as_ref(), WRITING_FILES, deleted.len() + written.len());
+        for path in deleted {
+            self.fs.delete(path).await.into_js_result()?;
+            progress.advance(1, 0, Some(path));
+        }
+        for (path, obj_hash) in written {
+            let buf = self.repository().read_file_obj(obj_hash).await?;
+            self.fs.write_file(path, &buf).await.into_js_result()?;
+            progress.advance(1, buf.len(), Some(path));
+        }
+        Ok(())
+    }
+}
+
+
+/// Whether `file` is `path` itself or a file under the directory `path`.
+#[inline]
+pub fn is_under(file: &str, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    file == path || file.strip_prefix(path).is_some_and(|rest| rest.starts_with('/'))
+}